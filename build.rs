@@ -35,6 +35,18 @@ const INCLUDED_TYPES: &[&str] = &[
     "pte_t",
     "pteval_t",
     "pid_t",
+    "wait_queue_head_t",
+    "poll_table",
+    "vm_operations_struct",
+    "vm_fault",
+    "pgprot_t",
+    "page",
+    "iov_iter",
+    "kiocb",
+    "miscdevice",
+    "seq_file",
+    "seq_operations",
+    "debugfs_blob_wrapper",
 ];
 const INCLUDED_FUNCTIONS: &[&str] = &[
     "cdev_add",
@@ -47,6 +59,7 @@ const INCLUDED_FUNCTIONS: &[&str] = &[
     "mount_nodev",
     "kill_litter_super",
     "register_sysctl",
+    "register_sysctl_table",
     "unregister_sysctl_table",
     "access_ok",
     "_copy_to_user",
@@ -64,7 +77,15 @@ const INCLUDED_FUNCTIONS: &[&str] = &[
     "device_destroy",
     "proc_mkdir_mode",
     "proc_create",
+    "proc_create_data",
+    "proc_symlink",
+    "proc_set_size",
+    "proc_set_user",
     "proc_remove",
+    "debugfs_create_dir",
+    "debugfs_create_file",
+    "debugfs_create_blob",
+    "debugfs_remove_recursive",
     "find_vma",
     "down_read",
     "down_write",
@@ -81,14 +102,173 @@ const INCLUDED_FUNCTIONS: &[&str] = &[
     "pid_task",
     "rdmsr_safe_on_cpu",
     "wrmsr_safe_on_cpu",
+    "d_path",
+    "remap_pfn_range",
+    "vm_insert_page",
+    "__free_pages",
+    "misc_register",
+    "misc_deregister",
+    "seq_open",
+    "seq_read",
+    "seq_lseek",
+    "seq_release",
+    "seq_write",
+    "single_open",
+    "single_release",
+    "generic_file_splice_read",
+    "iter_file_splice_write",
 ];
 const INCLUDED_VARS: &[&str] = &[
-    "EINVAL",
+    "EPERM",
+    "ENOENT",
+    "ESRCH",
+    "EINTR",
+    "EIO",
+    "ENXIO",
+    "E2BIG",
+    "ENOEXEC",
+    "EBADF",
+    "ECHILD",
+    "EAGAIN",
     "ENOMEM",
-    "ESPIPE",
+    "EACCES",
     "EFAULT",
-    "EAGAIN",
-    "ENOENT",
+    "ENOTBLK",
+    "EBUSY",
+    "EEXIST",
+    "EXDEV",
+    "ENODEV",
+    "ENOTDIR",
+    "EISDIR",
+    "EINVAL",
+    "ENFILE",
+    "EMFILE",
+    "ENOTTY",
+    "ETXTBSY",
+    "EFBIG",
+    "ENOSPC",
+    "ESPIPE",
+    "EROFS",
+    "EMLINK",
+    "EPIPE",
+    "EDOM",
+    "ERANGE",
+    "EDEADLK",
+    "ENAMETOOLONG",
+    "ENOLCK",
+    "ENOSYS",
+    "ENOTEMPTY",
+    "ELOOP",
+    "ENOMSG",
+    "EIDRM",
+    "ECHRNG",
+    "EL2NSYNC",
+    "EL3HLT",
+    "EL3RST",
+    "ELNRNG",
+    "EUNATCH",
+    "ENOCSI",
+    "EL2HLT",
+    "EBADE",
+    "EBADR",
+    "EXFULL",
+    "ENOANO",
+    "EBADRQC",
+    "EBADSLT",
+    "EBFONT",
+    "ENOSTR",
+    "ENODATA",
+    "ETIME",
+    "ENOSR",
+    "ENONET",
+    "ENOPKG",
+    "EREMOTE",
+    "ENOLINK",
+    "EADV",
+    "ESRMNT",
+    "ECOMM",
+    "EPROTO",
+    "EMULTIHOP",
+    "EDOTDOT",
+    "EBADMSG",
+    "EOVERFLOW",
+    "ENOTUNIQ",
+    "EBADFD",
+    "EREMCHG",
+    "ELIBACC",
+    "ELIBBAD",
+    "ELIBSCN",
+    "ELIBMAX",
+    "ELIBEXEC",
+    "EILSEQ",
+    "ERESTART",
+    "ESTRPIPE",
+    "EUSERS",
+    "ENOTSOCK",
+    "EDESTADDRREQ",
+    "EMSGSIZE",
+    "EPROTOTYPE",
+    "ENOPROTOOPT",
+    "EPROTONOSUPPORT",
+    "ESOCKTNOSUPPORT",
+    "EOPNOTSUPP",
+    "EPFNOSUPPORT",
+    "EAFNOSUPPORT",
+    "EADDRINUSE",
+    "EADDRNOTAVAIL",
+    "ENETDOWN",
+    "ENETUNREACH",
+    "ENETRESET",
+    "ECONNABORTED",
+    "ECONNRESET",
+    "ENOBUFS",
+    "EISCONN",
+    "ENOTCONN",
+    "ESHUTDOWN",
+    "ETOOMANYREFS",
+    "ETIMEDOUT",
+    "ECONNREFUSED",
+    "EHOSTDOWN",
+    "EHOSTUNREACH",
+    "EALREADY",
+    "EINPROGRESS",
+    "ESTALE",
+    "EUCLEAN",
+    "ENOTNAM",
+    "ENAVAIL",
+    "EISNAM",
+    "EREMOTEIO",
+    "EDQUOT",
+    "ENOMEDIUM",
+    "EMEDIUMTYPE",
+    "ECANCELED",
+    "ENOKEY",
+    "EKEYEXPIRED",
+    "EKEYREVOKED",
+    "EKEYREJECTED",
+    "EOWNERDEAD",
+    "ENOTRECOVERABLE",
+    "ERFKILL",
+    "EHWPOISON",
+    "ERESTARTSYS",
+    "ERESTARTNOINTR",
+    "ERESTARTNOHAND",
+    "ENOIOCTLCMD",
+    "ERESTART_RESTARTBLOCK",
+    "EPROBE_DEFER",
+    "EOPENSTALE",
+    "EBADHANDLE",
+    "ENOTSYNC",
+    "EBADCOOKIE",
+    "ENOTSUPP",
+    "ETOOSMALL",
+    "ESERVERFAULT",
+    "EBADTYPE",
+    "EJUKEBOX",
+    "EIOCBQUEUED",
+    "ERECALLCONFLICT",
+    "EWOULDBLOCK",
+    "EDEADLOCK",
     "__this_module",
     "FS_REQUIRES_DEV",
     "FS_BINARY_MOUNTDATA",
@@ -96,16 +276,96 @@ const INCLUDED_VARS: &[&str] = &[
     "FS_USERNS_MOUNT",
     "FS_RENAME_DOES_D_MOVE",
     "BINDINGS_GFP_KERNEL",
+    "BINDINGS___GFP_ZERO",
+    "BINDINGS_PAGE_SIZE",
+    "BINDINGS_VM_FAULT_OOM",
+    "BINDINGS_VM_FAULT_SIGBUS",
+    "BINDINGS_VM_FAULT_NOPAGE",
+    "KERN_EMERG",
+    "KERN_ALERT",
+    "KERN_CRIT",
+    "KERN_ERR",
+    "KERN_WARNING",
+    "KERN_NOTICE",
     "KERN_INFO",
+    "KERN_DEBUG",
+    "KERN_CONT",
+    "DEFAULT_RATELIMIT_INTERVAL",
+    "DEFAULT_RATELIMIT_BURST",
+    "jiffies",
     "VERIFY_WRITE",
     "LINUX_VERSION_CODE",
+    "S_IFMT",
+    "S_IFSOCK",
+    "S_IFLNK",
+    "S_IFREG",
+    "S_IFBLK",
+    "S_IFDIR",
+    "S_IFCHR",
+    "S_IFIFO",
+    "S_ISUID",
+    "S_ISGID",
+    "S_ISVTX",
+    "S_IRWXU",
+    "S_IRUSR",
+    "S_IWUSR",
+    "S_IXUSR",
+    "S_IRWXG",
+    "S_IRGRP",
+    "S_IWGRP",
+    "S_IXGRP",
+    "S_IRWXO",
+    "S_IROTH",
+    "S_IWOTH",
+    "S_IXOTH",
+    "S_IRWXUGO",
+    "S_IALLUGO",
+    "S_IRUGO",
+    "S_IWUGO",
+    "S_IXUGO",
     "SEEK_SET",
     "SEEK_CUR",
     "SEEK_END",
+    "O_ACCMODE",
+    "O_WRONLY",
+    "O_RDWR",
+    "O_CREAT",
+    "O_EXCL",
+    "O_NOCTTY",
+    "O_TRUNC",
+    "O_APPEND",
     "O_NONBLOCK",
+    "O_DSYNC",
+    "O_SYNC",
+    "FASYNC",
+    "O_DIRECT",
+    "O_LARGEFILE",
+    "O_DIRECTORY",
+    "O_NOFOLLOW",
+    "O_NOATIME",
+    "O_CLOEXEC",
+    "O_PATH",
+    "O_TMPFILE",
+    "BINDINGS_FMODE_READ",
+    "BINDINGS_FMODE_WRITE",
+    "BINDINGS_FMODE_LSEEK",
+    "BINDINGS_FMODE_PREAD",
+    "BINDINGS_FMODE_PWRITE",
+    "BINDINGS_FMODE_EXEC",
+    "MISC_DYNAMIC_MINOR",
     "phys_base",
     "page_offset_base",
     "current_task",
+    "POLLIN",
+    "POLLPRI",
+    "POLLOUT",
+    "POLLERR",
+    "POLLHUP",
+    "POLLNVAL",
+    "POLLRDNORM",
+    "POLLRDBAND",
+    "POLLWRNORM",
+    "POLLWRBAND",
 ];
 const OPAQUE_TYPES: &[&str] = &[
     // These need to be opaque because they're both packed and aligned, which rustc
@@ -12,3 +12,5 @@ mod bindings {
 pub use bindings::*;
 
 pub const GFP_KERNEL: gfp_t = BINDINGS_GFP_KERNEL;
+pub const __GFP_ZERO: gfp_t = BINDINGS___GFP_ZERO;
+pub const PAGE_SIZE: usize = BINDINGS_PAGE_SIZE as usize;
@@ -1,9 +1,16 @@
 #include <linux/cdev.h>
+#include <linux/debugfs.h>
 #include <linux/fs.h>
+#include <linux/miscdevice.h>
+#include <linux/mm.h>
 #include <linux/module.h>
+#include <linux/poll.h>
 #include <linux/random.h>
+#include <linux/seq_file.h>
 #include <linux/slab.h>
+#include <linux/uio.h>
 #include <linux/uaccess.h>
+#include <linux/wait.h>
 #include <linux/version.h>
 #include <linux/pid.h>
 #include <linux/proc_fs.h>
@@ -18,3 +25,14 @@
 // Bindgen gets confused at certain things
 //
 const gfp_t BINDINGS_GFP_KERNEL = GFP_KERNEL;
+const gfp_t BINDINGS___GFP_ZERO = __GFP_ZERO;
+const unsigned long BINDINGS_PAGE_SIZE = PAGE_SIZE;
+const fmode_t BINDINGS_FMODE_READ = FMODE_READ;
+const fmode_t BINDINGS_FMODE_WRITE = FMODE_WRITE;
+const fmode_t BINDINGS_FMODE_LSEEK = FMODE_LSEEK;
+const fmode_t BINDINGS_FMODE_PREAD = FMODE_PREAD;
+const fmode_t BINDINGS_FMODE_PWRITE = FMODE_PWRITE;
+const fmode_t BINDINGS_FMODE_EXEC = FMODE_EXEC;
+const unsigned int BINDINGS_VM_FAULT_OOM = VM_FAULT_OOM;
+const unsigned int BINDINGS_VM_FAULT_SIGBUS = VM_FAULT_SIGBUS;
+const unsigned int BINDINGS_VM_FAULT_NOPAGE = VM_FAULT_NOPAGE;
@@ -3,37 +3,82 @@ use core::mem;
 use core::ops::Range;
 
 use alloc::boxed::Box;
+use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
 
+use intrusive_collections::container_of;
+
 use crate::bindings;
 use crate::c_types;
-use crate::device::DeviceNumber;
+use crate::device::{self, DeviceNumber};
 use crate::error::{Error, KernelResult};
-use crate::file_operations;
+use crate::file_operations::{self, Context, OpenAdapter};
 use crate::types::CStr;
 
-pub fn builder(name: CStr<'static>, minors: Range<u16>) -> KernelResult<Builder> {
+pub fn builder(name: CStr, minors: Range<u16>) -> KernelResult<Builder> {
     Ok(Builder {
         name,
         minors,
-        file_ops: vec![],
+        devices: vec![],
+        nodes: None,
     })
 }
 
-pub struct Builder {
-    name: CStr<'static>,
+struct Device {
+    file_ops: &'static bindings::file_operations,
+    context: Context,
+}
+
+struct Cdev {
+    cdev: bindings::cdev,
+    context: Context,
+}
+
+struct CdevAdapter;
+
+impl OpenAdapter for CdevAdapter {
+    unsafe fn context(
+        inode: *mut bindings::inode,
+        _file: *mut bindings::file,
+    ) -> *const c_types::c_void {
+        let cdev: *const Cdev = container_of!((*inode).i_cdev, Cdev, cdev);
+        (*cdev).context.ptr
+    }
+}
+
+pub struct Builder<'a> {
+    name: CStr<'a>,
     minors: Range<u16>,
-    file_ops: Vec<&'static bindings::file_operations>,
+    devices: Vec<Device>,
+    nodes: Option<(CStr<'a>, &'a str)>,
 }
 
-impl Builder {
-    pub fn register_device<T: file_operations::FileOperations>(mut self) -> Builder {
-        if self.file_ops.len() >= self.minors.len() {
+impl<'a> Builder<'a> {
+    /// Registers `T` for the next minor number. Every file opened through
+    /// that minor receives `context` in [`FileOperations::open`].
+    ///
+    /// [`FileOperations::open`]: file_operations::FileOperations::open
+    pub fn register_device<T: file_operations::FileOperations>(
+        mut self,
+        context: Arc<T::Context>,
+    ) -> Builder<'a> {
+        if self.devices.len() >= self.minors.len() {
             panic!("More devices registered than minor numbers allocated.")
         }
-        self.file_ops
-            .push(&file_operations::FileOperationsVtable::<T>::VTABLE);
+        self.devices.push(Device {
+            file_ops: &file_operations::FileOperationsVtable::<CdevAdapter, T>::VTABLE,
+            context: Context::new(context),
+        });
+        self
+    }
+
+    /// Creates a device class named `class_name` and a `/dev` node for every
+    /// registered device when the registration is built. Each node is named
+    /// `node_prefix` followed by its minor number, e.g. `mydev0`. The nodes
+    /// and the class are removed when the [`Registration`] is dropped.
+    pub fn create_nodes(mut self, class_name: CStr<'a>, node_prefix: &'a str) -> Builder<'a> {
+        self.nodes = Some((class_name, node_prefix));
         self
     }
 
@@ -51,37 +96,71 @@ impl Builder {
             return Err(Error::from_kernel_errno(res));
         }
 
+        let count = self.minors.len();
+        let nodes = self.nodes;
+        let file_ops = self
+            .devices
+            .iter()
+            .map(|device| device.file_ops)
+            .collect::<Vec<_>>();
         // Turn this into a boxed slice immediately because the kernel stores pointers into it, and
         // so that data should never be moved.
-        let mut cdevs = vec![unsafe { mem::zeroed() }; self.file_ops.len()].into_boxed_slice();
-        for (i, file_op) in self.file_ops.iter().enumerate() {
+        let mut cdevs = self
+            .devices
+            .into_iter()
+            .map(|device| Cdev {
+                cdev: unsafe { mem::zeroed() },
+                context: device.context,
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        for (i, file_op) in file_ops.iter().enumerate() {
             unsafe {
-                bindings::cdev_init(&mut cdevs[i], *file_op);
-                cdevs[i].owner = &mut bindings::__this_module;
-                let rc = bindings::cdev_add(&mut cdevs[i], dev + i as bindings::dev_t, 1);
+                bindings::cdev_init(&mut cdevs[i].cdev, *file_op);
+                cdevs[i].cdev.owner = &mut bindings::__this_module;
+                let rc = bindings::cdev_add(&mut cdevs[i].cdev, dev + i as bindings::dev_t, 1);
                 if rc != 0 {
                     // Clean up the ones that were allocated.
                     for j in 0..=i {
-                        bindings::cdev_del(&mut cdevs[j]);
+                        bindings::cdev_del(&mut cdevs[j].cdev);
                     }
-                    bindings::unregister_chrdev_region(dev, self.minors.len() as _);
+                    bindings::unregister_chrdev_region(dev, count as _);
                     return Err(Error::from_kernel_errno(rc));
                 }
             }
         }
 
-        Ok(Registration {
+        let mut registration = Registration {
             dev,
-            count: self.minors.len(),
+            count,
             cdevs,
-        })
+            nodes: vec![],
+        };
+
+        // From here on, dropping the registration cleans up everything that
+        // was created so far.
+        if let Some((class_name, node_prefix)) = nodes {
+            let class = Arc::new(device::Class::create(class_name)?);
+            for i in 0..registration.cdevs.len() {
+                let number = DeviceNumber::from(dev + i as bindings::dev_t);
+                let name = crate::kformat!("{}{}", node_prefix, number.minor())?;
+                registration.nodes.push(device::Device::new(
+                    class.clone(),
+                    name.as_cstr(),
+                    number,
+                )?);
+            }
+        }
+
+        Ok(registration)
     }
 }
 
 pub struct Registration {
     dev: bindings::dev_t,
     count: usize,
-    cdevs: Box<[bindings::cdev]>,
+    cdevs: Box<[Cdev]>,
+    nodes: Vec<device::Device>,
 }
 
 // This is safe because Registration doesn't actually expose any methods.
@@ -95,9 +174,12 @@ impl Registration {
 
 impl Drop for Registration {
     fn drop(&mut self) {
+        // Remove the device nodes (and with the last of them, the class)
+        // before the devices they refer to.
+        self.nodes.clear();
         unsafe {
             for dev in self.cdevs.iter_mut() {
-                bindings::cdev_del(dev);
+                bindings::cdev_del(&mut dev.cdev);
             }
             bindings::unregister_chrdev_region(self.dev, self.count as _);
         }
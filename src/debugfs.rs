@@ -0,0 +1,248 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64};
+
+use crate::bindings;
+use crate::c_types;
+use crate::error::{from_err_ptr, Error, KernelResult};
+use crate::file_operations::{Context, FileOperations, FileOperationsVtable, OpenAdapter};
+use crate::types::{CStr, Mode};
+
+extern "C" {
+    fn debugfs_create_u32_helper(
+        name: *const c_types::c_char,
+        mode: bindings::umode_t,
+        parent: *mut bindings::dentry,
+        value: *mut u32,
+    ) -> c_types::c_int;
+    fn debugfs_create_x32_helper(
+        name: *const c_types::c_char,
+        mode: bindings::umode_t,
+        parent: *mut bindings::dentry,
+        value: *mut u32,
+    ) -> c_types::c_int;
+    fn debugfs_create_u64_helper(
+        name: *const c_types::c_char,
+        mode: bindings::umode_t,
+        parent: *mut bindings::dentry,
+        value: *mut u64,
+    ) -> c_types::c_int;
+    fn debugfs_create_bool_helper(
+        name: *const c_types::c_char,
+        mode: bindings::umode_t,
+        parent: *mut bindings::dentry,
+        value: *mut bool,
+    ) -> c_types::c_int;
+}
+
+// Newer kernels no longer report failures of the typed creators, in which
+// case the helpers return 0.
+fn check_created(res: c_types::c_int) -> KernelResult<()> {
+    if res != 0 {
+        return Err(Error::from_kernel_errno(res));
+    }
+    Ok(())
+}
+
+struct DebugfsAdapter;
+
+impl OpenAdapter for DebugfsAdapter {
+    unsafe fn context(
+        inode: *mut bindings::inode,
+        _file: *mut bindings::file,
+    ) -> *const c_types::c_void {
+        // debugfs_create_file() stores its data argument in the inode.
+        (*inode).i_private
+    }
+}
+
+/// A directory in debugfs, created with `debugfs_create_dir`. Dropping it
+/// removes the directory and everything in it with
+/// `debugfs_remove_recursive`. Subdirectories are owned by their parent, so
+/// they cannot outlive it.
+pub struct Dir {
+    dentry: *mut bindings::dentry,
+    subdirectories: Vec<Dir>,
+    // Responsible for keeping the data of the files in the directory alive
+    // until they have been removed.
+    _contexts: Vec<Context>,
+    _blobs: Vec<Box<bindings::debugfs_blob_wrapper>>,
+}
+
+// Through a shared reference the directory is never used, since creating
+// anything in it takes `&mut self`. The contexts are `Send + Sync`, as
+// required by `create_file`.
+unsafe impl Sync for Dir {}
+
+impl Dir {
+    /// Creates a directory named `name` at the root of debugfs.
+    ///
+    /// Returns ENODEV if debugfs is not available.
+    pub fn new(name: CStr) -> KernelResult<Self> {
+        Self::create(name, ptr::null_mut())
+    }
+
+    /// Creates a subdirectory named `name`, which is removed along with this
+    /// directory.
+    pub fn create_dir(&mut self, name: CStr) -> KernelResult<&mut Dir> {
+        let dir = Self::create(name, self.dentry)?;
+        self.subdirectories.push(dir);
+        Ok(self.subdirectories.last_mut().unwrap())
+    }
+
+    fn create(name: CStr, parent: *mut bindings::dentry) -> KernelResult<Self> {
+        let dentry = from_err_ptr(unsafe {
+            bindings::debugfs_create_dir(name.as_ptr() as *const c_types::c_char, parent)
+        })?;
+        // Older kernels return NULL instead of an error pointer.
+        if dentry.is_null() {
+            return Err(Error::ENOMEM);
+        }
+
+        Ok(Self {
+            dentry,
+            subdirectories: Vec::new(),
+            _contexts: Vec::new(),
+            _blobs: Vec::new(),
+        })
+    }
+
+    /// Creates a file named `name` implemented by `T`. Every time it is
+    /// opened, [`FileOperations::open`] receives `context`, which is released
+    /// when the directory is dropped. Corresponds to `debugfs_create_file`.
+    pub fn create_file<T: FileOperations>(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+        context: Arc<T::Context>,
+    ) -> KernelResult<()> {
+        let context = Context::new(context);
+        let dentry = from_err_ptr(unsafe {
+            bindings::debugfs_create_file(
+                name.as_ptr() as *const c_types::c_char,
+                mode.as_int(),
+                self.dentry,
+                context.ptr as *mut c_types::c_void,
+                &FileOperationsVtable::<DebugfsAdapter, T>::VTABLE,
+            )
+        })?;
+        if dentry.is_null() {
+            return Err(Error::ENOMEM);
+        }
+
+        self._contexts.push(context);
+        Ok(())
+    }
+
+    /// Creates a file named `name` that reads and writes `value` in decimal.
+    /// Corresponds to `debugfs_create_u32`.
+    pub fn create_u32(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+        value: &'static AtomicU32,
+    ) -> KernelResult<()> {
+        check_created(unsafe {
+            debugfs_create_u32_helper(
+                name.as_ptr() as *const c_types::c_char,
+                mode.as_int(),
+                self.dentry,
+                value as *const AtomicU32 as *mut u32,
+            )
+        })
+    }
+
+    /// Creates a file named `name` that reads and writes `value` in
+    /// hexadecimal. Corresponds to `debugfs_create_x32`.
+    pub fn create_x32(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+        value: &'static AtomicU32,
+    ) -> KernelResult<()> {
+        check_created(unsafe {
+            debugfs_create_x32_helper(
+                name.as_ptr() as *const c_types::c_char,
+                mode.as_int(),
+                self.dentry,
+                value as *const AtomicU32 as *mut u32,
+            )
+        })
+    }
+
+    /// Creates a file named `name` that reads and writes `value` in decimal.
+    /// Corresponds to `debugfs_create_u64`.
+    pub fn create_u64(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+        value: &'static AtomicU64,
+    ) -> KernelResult<()> {
+        check_created(unsafe {
+            debugfs_create_u64_helper(
+                name.as_ptr() as *const c_types::c_char,
+                mode.as_int(),
+                self.dentry,
+                value as *const AtomicU64 as *mut u64,
+            )
+        })
+    }
+
+    /// Creates a file named `name` that reads `value` as `Y` or `N`, and
+    /// accepts anything `kstrtobool` does. Corresponds to
+    /// `debugfs_create_bool`.
+    pub fn create_bool(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+        value: &'static AtomicBool,
+    ) -> KernelResult<()> {
+        check_created(unsafe {
+            debugfs_create_bool_helper(
+                name.as_ptr() as *const c_types::c_char,
+                mode.as_int(),
+                self.dentry,
+                value as *const AtomicBool as *mut bool,
+            )
+        })
+    }
+
+    /// Creates a read-only file named `name` with the contents of `data`.
+    /// The write bits of `mode` are ignored. Corresponds to
+    /// `debugfs_create_blob`.
+    pub fn create_blob(&mut self, name: CStr, mode: Mode, data: &'static [u8]) -> KernelResult<()> {
+        let mut blob = Box::new(bindings::debugfs_blob_wrapper {
+            data: data.as_ptr() as *mut c_types::c_void,
+            size: data.len() as c_types::c_ulong,
+        });
+        let dentry = from_err_ptr(unsafe {
+            bindings::debugfs_create_blob(
+                name.as_ptr() as *const c_types::c_char,
+                // `data` is immutable, but newer kernels let userspace write
+                // to blobs whose mode allows it.
+                (mode & !Mode::S_IWUGO).as_int(),
+                self.dentry,
+                &mut *blob,
+            )
+        })?;
+        if dentry.is_null() {
+            return Err(Error::ENOMEM);
+        }
+
+        self._blobs.push(blob);
+        Ok(())
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        // Subdirectories remove themselves first, while their dentries are
+        // still valid.
+        self.subdirectories.clear();
+        unsafe {
+            bindings::debugfs_remove_recursive(self.dentry);
+        }
+    }
+}
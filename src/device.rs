@@ -1,28 +1,45 @@
-use alloc::sync::Arc;
 use crate::bindings;
 use crate::c_types;
-use crate::types::CStr;
+use crate::error::{from_err_ptr, KernelResult};
+use crate::types::{CStr, CString};
+use alloc::sync::Arc;
+
+extern "C" {
+    fn class_create_helper(name: *const c_types::c_char) -> *mut bindings::class;
+}
 
 pub struct Class {
     class: *mut bindings::class,
+    // The class keeps a pointer to its name.
+    _name: CString,
 }
 
 unsafe impl Send for Class {}
 unsafe impl Sync for Class {}
 
 impl Class {
-    pub fn new(name: CStr<'static>, key: &mut bindings::lock_class_key) -> Self {
-        let class = unsafe {
+    pub fn new(name: CStr, key: &mut bindings::lock_class_key) -> KernelResult<Self> {
+        let name = CString::from_cstr(name)?;
+        let class = from_err_ptr(unsafe {
             bindings::__class_create(
                 &mut bindings::__this_module,
                 name.as_ptr() as *const c_types::c_char,
                 key as *mut bindings::lock_class_key,
             )
-        };
+        })?;
 
-        Self {
-            class,
-        }
+        Ok(Self { class, _name: name })
+    }
+
+    /// Creates a new device class. Unlike [`Class::new`], all classes created
+    /// this way share a single lock class key. Corresponds to
+    /// `class_create`.
+    pub fn create(name: CStr) -> KernelResult<Self> {
+        let name = CString::from_cstr(name)?;
+        let class =
+            from_err_ptr(unsafe { class_create_helper(name.as_ptr() as *const c_types::c_char) })?;
+
+        Ok(Self { class, _name: name })
     }
 }
 
@@ -68,26 +85,25 @@ pub struct Device {
 unsafe impl Sync for Device {}
 
 impl Device {
-    pub fn new(
-        class: Arc<Class>,
-        name: CStr<'static>,
-        dev: DeviceNumber,
-    ) -> Self {
-        let device = unsafe {
+    pub fn new(class: Arc<Class>, name: CStr, dev: DeviceNumber) -> KernelResult<Self> {
+        // Pass the name as an argument, so that it is not interpreted as a
+        // format string.
+        let device = from_err_ptr(unsafe {
             bindings::device_create(
                 class.class,
                 core::ptr::null_mut(),
                 dev.into(),
                 core::ptr::null_mut(),
+                b"%s\0".as_ptr() as *const c_types::c_char,
                 name.as_ptr() as *const c_types::c_char,
             )
-        };
+        })?;
 
-        Self {
+        Ok(Self {
             class: class,
             _device: device,
             dev,
-        }
+        })
     }
 }
 
@@ -1,17 +1,199 @@
+use core::alloc::AllocErr;
+use core::fmt;
 use core::num::TryFromIntError;
+use core::str::Utf8Error;
 
 use crate::bindings;
 use crate::c_types;
 
+/// A kernel error code, stored as a negative errno.
+///
+/// `Debug` and `Display` print its symbolic name and value, e.g.
+/// `EBUSY (-16)`.
 pub struct Error(c_types::c_int);
 
+macro_rules! declare_errors {
+    ($($name:ident,)*) => {
+        impl Error {
+            $(
+                pub const $name: Self = Error(-(bindings::$name as i32));
+            )*
+
+            /// Returns the symbolic name of this error, e.g. `"EBUSY"`, or
+            /// `None` if the value is not a known errno.
+            pub fn name(&self) -> Option<&'static str> {
+                match self.0.wrapping_neg() as u32 {
+                    $(
+                        bindings::$name => Some(stringify!($name)),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+declare_errors! {
+    // From include/uapi/asm-generic/errno-base.h
+    EPERM,
+    ENOENT,
+    ESRCH,
+    EINTR,
+    EIO,
+    ENXIO,
+    E2BIG,
+    ENOEXEC,
+    EBADF,
+    ECHILD,
+    EAGAIN,
+    ENOMEM,
+    EACCES,
+    EFAULT,
+    ENOTBLK,
+    EBUSY,
+    EEXIST,
+    EXDEV,
+    ENODEV,
+    ENOTDIR,
+    EISDIR,
+    EINVAL,
+    ENFILE,
+    EMFILE,
+    ENOTTY,
+    ETXTBSY,
+    EFBIG,
+    ENOSPC,
+    ESPIPE,
+    EROFS,
+    EMLINK,
+    EPIPE,
+    EDOM,
+    ERANGE,
+
+    // From include/uapi/asm-generic/errno.h
+    EDEADLK,
+    ENAMETOOLONG,
+    ENOLCK,
+    ENOSYS,
+    ENOTEMPTY,
+    ELOOP,
+    ENOMSG,
+    EIDRM,
+    ECHRNG,
+    EL2NSYNC,
+    EL3HLT,
+    EL3RST,
+    ELNRNG,
+    EUNATCH,
+    ENOCSI,
+    EL2HLT,
+    EBADE,
+    EBADR,
+    EXFULL,
+    ENOANO,
+    EBADRQC,
+    EBADSLT,
+    EBFONT,
+    ENOSTR,
+    ENODATA,
+    ETIME,
+    ENOSR,
+    ENONET,
+    ENOPKG,
+    EREMOTE,
+    ENOLINK,
+    EADV,
+    ESRMNT,
+    ECOMM,
+    EPROTO,
+    EMULTIHOP,
+    EDOTDOT,
+    EBADMSG,
+    EOVERFLOW,
+    ENOTUNIQ,
+    EBADFD,
+    EREMCHG,
+    ELIBACC,
+    ELIBBAD,
+    ELIBSCN,
+    ELIBMAX,
+    ELIBEXEC,
+    EILSEQ,
+    ERESTART,
+    ESTRPIPE,
+    EUSERS,
+    ENOTSOCK,
+    EDESTADDRREQ,
+    EMSGSIZE,
+    EPROTOTYPE,
+    ENOPROTOOPT,
+    EPROTONOSUPPORT,
+    ESOCKTNOSUPPORT,
+    EOPNOTSUPP,
+    EPFNOSUPPORT,
+    EAFNOSUPPORT,
+    EADDRINUSE,
+    EADDRNOTAVAIL,
+    ENETDOWN,
+    ENETUNREACH,
+    ENETRESET,
+    ECONNABORTED,
+    ECONNRESET,
+    ENOBUFS,
+    EISCONN,
+    ENOTCONN,
+    ESHUTDOWN,
+    ETOOMANYREFS,
+    ETIMEDOUT,
+    ECONNREFUSED,
+    EHOSTDOWN,
+    EHOSTUNREACH,
+    EALREADY,
+    EINPROGRESS,
+    ESTALE,
+    EUCLEAN,
+    ENOTNAM,
+    ENAVAIL,
+    EISNAM,
+    EREMOTEIO,
+    EDQUOT,
+    ENOMEDIUM,
+    EMEDIUMTYPE,
+    ECANCELED,
+    ENOKEY,
+    EKEYEXPIRED,
+    EKEYREVOKED,
+    EKEYREJECTED,
+    EOWNERDEAD,
+    ENOTRECOVERABLE,
+    ERFKILL,
+    EHWPOISON,
+
+    // From include/linux/errno.h. These are internal to the kernel and must
+    // not reach userspace, except for the restart codes, which are handled
+    // by the signal code.
+    ERESTARTSYS,
+    ERESTARTNOINTR,
+    ERESTARTNOHAND,
+    ENOIOCTLCMD,
+    ERESTART_RESTARTBLOCK,
+    EPROBE_DEFER,
+    EOPENSTALE,
+    EBADHANDLE,
+    ENOTSYNC,
+    EBADCOOKIE,
+    ENOTSUPP,
+    ETOOSMALL,
+    ESERVERFAULT,
+    EBADTYPE,
+    EJUKEBOX,
+    EIOCBQUEUED,
+    ERECALLCONFLICT,
+}
+
 impl Error {
-    pub const EINVAL: Self = Error(-(bindings::EINVAL as i32));
-    pub const ENOMEM: Self = Error(-(bindings::ENOMEM as i32));
-    pub const EFAULT: Self = Error(-(bindings::EFAULT as i32));
-    pub const ESPIPE: Self = Error(-(bindings::ESPIPE as i32));
-    pub const EAGAIN: Self = Error(-(bindings::EAGAIN as i32));
-    pub const ENOENT: Self = Error(-(bindings::ENOENT as i32));
+    pub const EWOULDBLOCK: Self = Error::EAGAIN;
+    pub const EDEADLOCK: Self = Error::EDEADLK;
 
     pub fn from_kernel_errno(errno: c_types::c_int) -> Error {
         Error(errno)
@@ -22,10 +204,56 @@ impl Error {
     }
 }
 
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{} ({})", name, self.0),
+            None => write!(f, "unknown error ({})", self.0),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
 impl From<TryFromIntError> for Error {
     fn from(_: TryFromIntError) -> Error {
         Error::EINVAL
     }
 }
 
+impl From<Utf8Error> for Error {
+    fn from(_: Utf8Error) -> Error {
+        Error::EINVAL
+    }
+}
+
+impl From<AllocErr> for Error {
+    fn from(_: AllocErr) -> Error {
+        Error::ENOMEM
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Error {
+        Error::EINVAL
+    }
+}
+
 pub type KernelResult<T> = Result<T, Error>;
+
+// From include/linux/err.h
+const MAX_ERRNO: usize = 4095;
+
+/// Returns `ptr`, or the error it encodes if it is an `ERR_PTR`. Corresponds
+/// to `IS_ERR` and `PTR_ERR`.
+pub fn from_err_ptr<T>(ptr: *mut T) -> KernelResult<*mut T> {
+    if ptr as usize >= (-(MAX_ERRNO as isize)) as usize {
+        Err(Error::from_kernel_errno(ptr as isize as c_types::c_int))
+    } else {
+        Ok(ptr)
+    }
+}
@@ -2,16 +2,81 @@ use core::convert::{TryFrom, TryInto};
 use core::{marker, mem, ptr};
 
 use alloc::boxed::Box;
+use alloc::sync::Arc;
 
 use crate::bindings;
 use crate::c_types;
-use crate::error::{Error, KernelResult};
+use crate::device::DeviceNumber;
+use crate::error::{from_err_ptr, Error, KernelResult};
 use crate::ioctl::Ioctl;
+use crate::iov_iter::IovIter;
+use crate::types::FromRaw;
 use crate::user_ptr::{UserSlicePtr, UserSlicePtrReader, UserSlicePtrWriter};
+use crate::vma::VMA;
+use crate::wait_queue::WaitQueue;
+
+extern "C" {
+    fn poll_wait_helper(
+        file: *mut bindings::file,
+        wq: *mut bindings::wait_queue_head_t,
+        table: *mut bindings::poll_table,
+    );
+    fn compat_ptr_helper(arg: c_types::c_ulong) -> c_types::c_ulong;
+}
 
 bitflags::bitflags! {
+    /// The `O_*` flags the file was opened with. Use
+    /// [`FileFlags::ACCMODE`] to extract the access mode, where neither
+    /// `WRONLY` nor `RDWR` means read-only.
     pub struct FileFlags: c_types::c_uint {
+        const ACCMODE = bindings::O_ACCMODE;
+        const WRONLY = bindings::O_WRONLY;
+        const RDWR = bindings::O_RDWR;
+        const CREAT = bindings::O_CREAT;
+        const EXCL = bindings::O_EXCL;
+        const NOCTTY = bindings::O_NOCTTY;
+        const TRUNC = bindings::O_TRUNC;
+        const APPEND = bindings::O_APPEND;
         const NONBLOCK = bindings::O_NONBLOCK;
+        const DSYNC = bindings::O_DSYNC;
+        const SYNC = bindings::O_SYNC;
+        const ASYNC = bindings::FASYNC;
+        const DIRECT = bindings::O_DIRECT;
+        const LARGEFILE = bindings::O_LARGEFILE;
+        const DIRECTORY = bindings::O_DIRECTORY;
+        const NOFOLLOW = bindings::O_NOFOLLOW;
+        const NOATIME = bindings::O_NOATIME;
+        const CLOEXEC = bindings::O_CLOEXEC;
+        const PATH = bindings::O_PATH;
+        const TMPFILE = bindings::O_TMPFILE;
+    }
+}
+
+bitflags::bitflags! {
+    /// How the file may be accessed. Corresponds to the kernel's `fmode_t`.
+    pub struct FileMode: bindings::fmode_t {
+        const READ = bindings::BINDINGS_FMODE_READ;
+        const WRITE = bindings::BINDINGS_FMODE_WRITE;
+        const LSEEK = bindings::BINDINGS_FMODE_LSEEK;
+        const PREAD = bindings::BINDINGS_FMODE_PREAD;
+        const PWRITE = bindings::BINDINGS_FMODE_PWRITE;
+        const EXEC = bindings::BINDINGS_FMODE_EXEC;
+    }
+}
+
+bitflags::bitflags! {
+    /// The events reported by [`FileOperations::poll`].
+    pub struct PollFlags: c_types::c_uint {
+        const IN = bindings::POLLIN;
+        const PRI = bindings::POLLPRI;
+        const OUT = bindings::POLLOUT;
+        const ERR = bindings::POLLERR;
+        const HUP = bindings::POLLHUP;
+        const NVAL = bindings::POLLNVAL;
+        const RDNORM = bindings::POLLRDNORM;
+        const RDBAND = bindings::POLLRDBAND;
+        const WRNORM = bindings::POLLWRNORM;
+        const WRBAND = bindings::POLLWRBAND;
     }
 }
 
@@ -31,6 +96,118 @@ impl File {
     pub fn flags(&self) -> FileFlags {
         FileFlags::from_bits_truncate(unsafe { (*self.ptr).f_flags })
     }
+
+    pub fn mode(&self) -> FileMode {
+        FileMode::from_bits_truncate(unsafe { (*self.ptr).f_mode })
+    }
+
+    /// Returns the credentials of the task that opened the file.
+    pub fn cred(&self) -> Credentials {
+        Credentials {
+            ptr: unsafe { (*self.ptr).f_cred },
+        }
+    }
+
+    pub fn inode(&self) -> Inode {
+        unsafe { Inode::from_ptr((*self.ptr).f_inode) }
+    }
+
+    /// Writes the path of the file into `buf` and returns it. The path is
+    /// placed at the end of `buf`. Corresponds to `d_path`.
+    ///
+    /// Returns `ENAMETOOLONG` if `buf` is too small.
+    pub fn path<'a>(&self, buf: &'a mut [u8]) -> KernelResult<&'a [u8]> {
+        let len: c_types::c_int = buf.len().try_into()?;
+        let start = from_err_ptr(unsafe {
+            bindings::d_path(
+                &(*self.ptr).f_path,
+                buf.as_mut_ptr() as *mut c_types::c_char,
+                len,
+            )
+        })?;
+
+        // d_path NUL-terminates the path at the end of the buffer.
+        let offset = start as usize - buf.as_ptr() as usize;
+        Ok(&buf[offset..buf.len() - 1])
+    }
+}
+
+/// The credentials of a task. Corresponds to the kernel's `struct cred`.
+///
+/// IDs are returned as seen from the initial user namespace.
+pub struct Credentials {
+    ptr: *const bindings::cred,
+}
+
+impl Credentials {
+    pub fn uid(&self) -> u32 {
+        unsafe { (*self.ptr).uid.val }
+    }
+
+    pub fn gid(&self) -> u32 {
+        unsafe { (*self.ptr).gid.val }
+    }
+
+    pub fn euid(&self) -> u32 {
+        unsafe { (*self.ptr).euid.val }
+    }
+
+    pub fn egid(&self) -> u32 {
+        unsafe { (*self.ptr).egid.val }
+    }
+
+    pub fn fsuid(&self) -> u32 {
+        unsafe { (*self.ptr).fsuid.val }
+    }
+
+    pub fn fsgid(&self) -> u32 {
+        unsafe { (*self.ptr).fsgid.val }
+    }
+}
+
+pub struct Inode {
+    ptr: *const bindings::inode,
+}
+
+impl Inode {
+    pub(crate) unsafe fn from_ptr(ptr: *const bindings::inode) -> Inode {
+        Inode { ptr }
+    }
+
+    /// Returns the device number of the device node that was opened.
+    pub fn device_number(&self) -> DeviceNumber {
+        DeviceNumber::from(unsafe { (*self.ptr).i_rdev })
+    }
+
+    /// Returns the inode number.
+    pub fn ino(&self) -> u64 {
+        unsafe { (*self.ptr).i_ino }
+    }
+
+    /// Returns the size of the file in bytes.
+    pub fn size(&self) -> u64 {
+        unsafe { (*self.ptr).i_size as u64 }
+    }
+}
+
+/// The table passed to [`FileOperations::poll`]. Corresponds to the kernel's
+/// `poll_table`.
+pub struct PollTable {
+    ptr: *mut bindings::poll_table,
+}
+
+impl PollTable {
+    pub(crate) unsafe fn from_ptr(ptr: *mut bindings::poll_table) -> PollTable {
+        PollTable { ptr }
+    }
+
+    /// Registers `queue` with this table, so that the polling task is woken
+    /// up whenever `queue` is. Corresponds to `poll_wait`.
+    pub fn wait(&self, file: &File, queue: &WaitQueue) {
+        unsafe {
+            poll_wait_helper(file.ptr as *mut bindings::file, queue.as_ptr(), self.ptr);
+        }
+    }
 }
 
 // Matches std::io::SeekFrom in the Rust stdlib
@@ -40,11 +217,52 @@ pub enum SeekFrom {
     Current(i64),
 }
 
-unsafe extern "C" fn open_callback<T: FileOperations>(
-    _inode: *mut bindings::inode,
+/// An `Arc<T>` whose type has been erased, so that registrations with different
+/// contexts can be stored side by side.
+pub(crate) struct Context {
+    pub(crate) ptr: *const c_types::c_void,
+    drop: unsafe fn(*const c_types::c_void),
+}
+
+unsafe fn drop_context<T>(ptr: *const c_types::c_void) {
+    drop(Arc::from_raw(ptr as *const T));
+}
+
+impl Context {
+    pub(crate) fn new<T>(context: Arc<T>) -> Context {
+        Context {
+            ptr: Arc::into_raw(context) as *const c_types::c_void,
+            drop: drop_context::<T>,
+        }
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.ptr) };
+    }
+}
+
+/// Locates the context of a file that is being opened. Implemented by each
+/// kind of registration that installs a [`FileOperationsVtable`].
+pub(crate) trait OpenAdapter {
+    /// Returns the context that was registered for `inode`, as obtained from
+    /// `Arc::into_raw`. The registration keeps its reference alive for as
+    /// long as the file can be opened.
+    unsafe fn context(
+        inode: *mut bindings::inode,
+        file: *mut bindings::file,
+    ) -> *const c_types::c_void;
+}
+
+unsafe extern "C" fn open_callback<A: OpenAdapter, T: FileOperations>(
+    inode: *mut bindings::inode,
     file: *mut bindings::file,
 ) -> c_types::c_int {
-    let f = match T::open() {
+    // Borrow the registration's reference rather than taking ownership of it.
+    let context =
+        mem::ManuallyDrop::new(Arc::from_raw(A::context(inode, file) as *const T::Context));
+    let f = match T::open(&context, &Inode::from_ptr(inode), &File::from_ptr(file)) {
         Ok(f) => Box::new(f),
         Err(e) => return e.to_kernel_errno(),
     };
@@ -106,6 +324,54 @@ unsafe extern "C" fn write_callback<T: FileOperations>(
     }
 }
 
+unsafe extern "C" fn read_iter_callback<T: FileOperations>(
+    iocb: *mut bindings::kiocb,
+    to: *mut bindings::iov_iter,
+) -> c_types::c_ssize_t {
+    let file = (*iocb).ki_filp;
+    let mut data = IovIter::from_ptr(to);
+    let len = data.len();
+    let f = &*((*file).private_data as *const T);
+    // No FMODE_UNSIGNED_OFFSET support, so offset must be in [0, 2^63).
+    // See discussion in #113
+    let positive_offset = match (*iocb).ki_pos.try_into() {
+        Ok(v) => v,
+        Err(_) => return Error::EINVAL.to_kernel_errno().try_into().unwrap(),
+    };
+    match f.read_iter(&File::from_ptr(file), &mut data, positive_offset) {
+        Ok(()) => {
+            let written = len - data.len();
+            (*iocb).ki_pos += bindings::loff_t::try_from(written).unwrap();
+            written.try_into().unwrap()
+        }
+        Err(e) => e.to_kernel_errno().try_into().unwrap(),
+    }
+}
+
+unsafe extern "C" fn write_iter_callback<T: FileOperations>(
+    iocb: *mut bindings::kiocb,
+    from: *mut bindings::iov_iter,
+) -> c_types::c_ssize_t {
+    let file = (*iocb).ki_filp;
+    let mut data = IovIter::from_ptr(from);
+    let len = data.len();
+    let f = &*((*file).private_data as *const T);
+    // No FMODE_UNSIGNED_OFFSET support, so offset must be in [0, 2^63).
+    // See discussion in #113
+    let positive_offset = match (*iocb).ki_pos.try_into() {
+        Ok(v) => v,
+        Err(_) => return Error::EINVAL.to_kernel_errno().try_into().unwrap(),
+    };
+    match f.write_iter(&File::from_ptr(file), &mut data, positive_offset) {
+        Ok(()) => {
+            let read = len - data.len();
+            (*iocb).ki_pos += bindings::loff_t::try_from(read).unwrap();
+            read.try_into().unwrap()
+        }
+        Err(e) => e.to_kernel_errno().try_into().unwrap(),
+    }
+}
+
 unsafe extern "C" fn release_callback<T: FileOperations>(
     _inode: *mut bindings::inode,
     file: *mut bindings::file,
@@ -149,68 +415,417 @@ unsafe extern "C" fn unlocked_ioctl_callback<T: FileOperations>(
     }
 }
 
-pub(crate) struct FileOperationsVtable<T>(marker::PhantomData<T>);
+unsafe extern "C" fn compat_ioctl_callback<T: FileOperations>(
+    file: *mut bindings::file,
+    num: c_types::c_uint,
+    param: c_types::c_ulong,
+) -> c_types::c_long {
+    let num = Ioctl::from(num);
+    let f = &*((*file).private_data as *const T);
+    match f.compat_ioctl(&File::from_ptr(file), num, param) {
+        Ok(ret) => ret as c_types::c_long,
+        Err(e) => e.to_kernel_errno().into(),
+    }
+}
+
+unsafe extern "C" fn poll_callback<T: FileOperations>(
+    file: *mut bindings::file,
+    table: *mut bindings::poll_table,
+) -> c_types::c_uint {
+    let f = &*((*file).private_data as *const T);
+    match f.poll(&File::from_ptr(file), &PollTable::from_ptr(table)) {
+        Ok(flags) => flags.bits(),
+        Err(_) => PollFlags::ERR.bits(),
+    }
+}
+
+unsafe extern "C" fn mmap_callback<T: FileOperations>(
+    file: *mut bindings::file,
+    vma: *mut bindings::vm_area_struct,
+) -> c_types::c_int {
+    let f = &*((*file).private_data as *const T);
+    let mut vma = VMA::from_raw(vma);
+    match f.mmap(&File::from_ptr(file), &mut vma) {
+        Ok(()) => {
+            vma.install_operations();
+            0
+        }
+        Err(e) => e.to_kernel_errno(),
+    }
+}
+
+/// Records which of the optional callbacks of a [`FileOperations`]
+/// implementation are used. The slots of the callbacks that are not used
+/// are left empty in the vtable, so that the kernel's default behaviour
+/// applies: for example an unseekable file fails `lseek` with `ESPIPE`, and
+/// a file without `read` fails it with `EINVAL`.
+///
+/// Usually declared with [`declare_file_operations!`].
+///
+/// [`declare_file_operations!`]: crate::declare_file_operations
+pub struct ToUse {
+    /// `read`.
+    pub read: bool,
+    /// `write`.
+    pub write: bool,
+    /// `read_iter` (or, since 5.10, `proc_read_iter`), and `splice_read` so
+    /// that `sendfile` and `splice` can read from the file.
+    pub read_iter: bool,
+    /// `write_iter`, and `splice_write` so that `splice` can write to the
+    /// file. Not supported by procfs.
+    pub write_iter: bool,
+    /// `llseek` (or `proc_lseek`).
+    pub seek: bool,
+    /// `unlocked_ioctl` (or `proc_ioctl`).
+    pub ioctl: bool,
+    /// `compat_ioctl` (or `proc_compat_ioctl`).
+    pub compat_ioctl: bool,
+    /// `poll` (or `proc_poll`).
+    pub poll: bool,
+    /// `mmap` (or `proc_mmap`).
+    pub mmap: bool,
+}
+
+/// A [`ToUse`] that uses none of the optional callbacks.
+pub const USE_NONE: ToUse = ToUse {
+    read: false,
+    write: false,
+    read_iter: false,
+    write_iter: false,
+    seek: false,
+    ioctl: false,
+    compat_ioctl: false,
+    poll: false,
+    mmap: false,
+};
+
+/// Declares the optional callbacks implemented by a [`FileOperations`]
+/// implementation, by defining its `TO_USE` constant. Takes the names of the
+/// fields of [`ToUse`] to enable.
+///
+/// Example:
+/// ```rust,ignore
+/// impl FileOperations for MyFile {
+///     type Context = ();
+///
+///     linux_kernel_module::declare_file_operations!(read, seek);
+///
+///     // ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! declare_file_operations {
+    () => {
+        const TO_USE: $crate::file_operations::ToUse = $crate::file_operations::USE_NONE;
+    };
+    ($($name:ident),+ $(,)?) => {
+        const TO_USE: $crate::file_operations::ToUse = $crate::file_operations::ToUse {
+            $($name: true),+,
+            ..$crate::file_operations::USE_NONE
+        };
+    };
+}
+
+// Builds a `struct file_operations` out of the callbacks this crate
+// installs, leaving every other member empty. Each argument is an
+// `Option` of the corresponding function pointer.
+macro_rules! file_operations_vtable {
+    (
+        open: $open:expr,
+        release: $release:expr,
+        read: $read:expr,
+        write: $write:expr,
+        read_iter: $read_iter:expr,
+        write_iter: $write_iter:expr,
+        llseek: $llseek:expr,
+        unlocked_ioctl: $unlocked_ioctl:expr,
+        compat_ioctl: $compat_ioctl:expr,
+        poll: $poll:expr,
+        mmap: $mmap:expr,
+        splice_read: $splice_read:expr,
+        splice_write: $splice_write:expr $(,)?
+    ) => {
+        $crate::bindings::file_operations {
+            open: $open,
+            release: $release,
+            read: $read,
+            write: $write,
+            read_iter: $read_iter,
+            write_iter: $write_iter,
+            llseek: $llseek,
+            unlocked_ioctl: $unlocked_ioctl,
+            compat_ioctl: $compat_ioctl,
+            poll: $poll,
+            mmap: $mmap,
+            // Before 4.9 the kernel falls back to `read_iter` by itself, and
+            // generic_file_splice_read() only works with the page cache.
+            #[cfg(kernel_4_9_0_or_greater)]
+            splice_read: $splice_read,
+            #[cfg(not(kernel_4_9_0_or_greater))]
+            splice_read: None,
+            splice_write: $splice_write,
+
+            #[cfg(not(kernel_4_9_0_or_greater))]
+            aio_fsync: None,
+            check_flags: None,
+            #[cfg(all(kernel_4_5_0_or_greater, not(kernel_4_20_0_or_greater)))]
+            clone_file_range: None,
+            #[cfg(kernel_4_5_0_or_greater)]
+            copy_file_range: None,
+            #[cfg(all(kernel_4_5_0_or_greater, not(kernel_4_20_0_or_greater)))]
+            dedupe_file_range: None,
+            fallocate: None,
+            #[cfg(kernel_4_19_0_or_greater)]
+            fadvise: None,
+            fasync: None,
+            flock: None,
+            flush: None,
+            fsync: None,
+            get_unmapped_area: None,
+            iterate: None,
+            #[cfg(kernel_4_7_0_or_greater)]
+            iterate_shared: None,
+            #[cfg(kernel_5_1_0_or_greater)]
+            iopoll: None,
+            lock: None,
+            #[cfg(kernel_4_15_0_or_greater)]
+            mmap_supported_flags: 0,
+            owner: core::ptr::null_mut(),
+            #[cfg(kernel_4_20_0_or_greater)]
+            remap_file_range: None,
+            sendpage: None,
+            #[cfg(kernel_aufs_setfl)]
+            setfl: None,
+            setlease: None,
+            show_fdinfo: None,
+        }
+    };
+}
+
+// Builds the vtable that `proc_create` expects on this kernel out of the
+// callbacks this crate installs, like `file_operations_vtable!`. Besides
+// the `FileOperations` vtables, seq_file uses it for its own callbacks.
+#[cfg(kernel_5_6_0_or_greater)]
+macro_rules! proc_vtable {
+    (
+        open: $open:expr,
+        release: $release:expr,
+        read: $read:expr,
+        read_iter: $read_iter:expr,
+        write: $write:expr,
+        llseek: $llseek:expr,
+        ioctl: $ioctl:expr,
+        compat_ioctl: $compat_ioctl:expr,
+        poll: $poll:expr,
+        mmap: $mmap:expr $(,)?
+    ) => {
+        $crate::bindings::proc_ops {
+            proc_flags: 0,
+
+            proc_open: $open,
+            proc_release: $release,
+            proc_read: $read,
+            #[cfg(kernel_5_10_0_or_greater)]
+            proc_read_iter: $read_iter,
+            proc_write: $write,
+            proc_lseek: $llseek,
+            proc_ioctl: $ioctl,
+            proc_compat_ioctl: $compat_ioctl,
+            proc_poll: $poll,
+            proc_mmap: $mmap,
+
+            proc_get_unmapped_area: None,
+        }
+    };
+}
+
+// Before 5.6, procfs takes a plain `struct file_operations`.
+#[cfg(not(kernel_5_6_0_or_greater))]
+macro_rules! proc_vtable {
+    (
+        open: $open:expr,
+        release: $release:expr,
+        read: $read:expr,
+        read_iter: $read_iter:expr,
+        write: $write:expr,
+        llseek: $llseek:expr,
+        ioctl: $ioctl:expr,
+        compat_ioctl: $compat_ioctl:expr,
+        poll: $poll:expr,
+        mmap: $mmap:expr $(,)?
+    ) => {
+        file_operations_vtable! {
+            open: $open,
+            release: $release,
+            read: $read,
+            write: $write,
+            read_iter: $read_iter,
+            write_iter: None,
+            llseek: $llseek,
+            unlocked_ioctl: $ioctl,
+            compat_ioctl: $compat_ioctl,
+            poll: $poll,
+            mmap: $mmap,
+            splice_read: None,
+            splice_write: None,
+        }
+    };
+}
+
+pub(crate) struct FileOperationsVtable<A, T>(marker::PhantomData<A>, marker::PhantomData<T>);
 
-impl<T: FileOperations> FileOperationsVtable<T> {
-    pub(crate) const VTABLE: bindings::file_operations = bindings::file_operations {
-        open: Some(open_callback::<T>),
+impl<A: OpenAdapter, T: FileOperations> FileOperationsVtable<A, T> {
+    pub(crate) const VTABLE: bindings::file_operations = file_operations_vtable! {
+        open: Some(open_callback::<A, T>),
         release: Some(release_callback::<T>),
-        read: Some(read_callback::<T>),
-        write: Some(write_callback::<T>),
-        llseek: Some(llseek_callback::<T>),
-        unlocked_ioctl: Some(unlocked_ioctl_callback::<T>),
-
-        #[cfg(not(kernel_4_9_0_or_greater))]
-        aio_fsync: None,
-        check_flags: None,
-        #[cfg(all(kernel_4_5_0_or_greater, not(kernel_4_20_0_or_greater)))]
-        clone_file_range: None,
-        compat_ioctl: None,
-        #[cfg(kernel_4_5_0_or_greater)]
-        copy_file_range: None,
-        #[cfg(all(kernel_4_5_0_or_greater, not(kernel_4_20_0_or_greater)))]
-        dedupe_file_range: None,
-        fallocate: None,
-        #[cfg(kernel_4_19_0_or_greater)]
-        fadvise: None,
-        fasync: None,
-        flock: None,
-        flush: None,
-        fsync: None,
-        get_unmapped_area: None,
-        iterate: None,
-        #[cfg(kernel_4_7_0_or_greater)]
-        iterate_shared: None,
-        #[cfg(kernel_5_1_0_or_greater)]
-        iopoll: None,
-        lock: None,
-        mmap: None,
-        #[cfg(kernel_4_15_0_or_greater)]
-        mmap_supported_flags: 0,
-        owner: ptr::null_mut(),
-        poll: None,
-        read_iter: None,
-        #[cfg(kernel_4_20_0_or_greater)]
-        remap_file_range: None,
-        sendpage: None,
-        #[cfg(kernel_aufs_setfl)]
-        setfl: None,
-        setlease: None,
-        show_fdinfo: None,
-        splice_read: None,
-        splice_write: None,
-        write_iter: None,
+        read: if T::TO_USE.read {
+            Some(read_callback::<T>)
+        } else {
+            None
+        },
+        write: if T::TO_USE.write {
+            Some(write_callback::<T>)
+        } else {
+            None
+        },
+        read_iter: if T::TO_USE.read_iter {
+            Some(read_iter_callback::<T>)
+        } else {
+            None
+        },
+        write_iter: if T::TO_USE.write_iter {
+            Some(write_iter_callback::<T>)
+        } else {
+            None
+        },
+        llseek: if T::TO_USE.seek {
+            Some(llseek_callback::<T>)
+        } else {
+            None
+        },
+        unlocked_ioctl: if T::TO_USE.ioctl {
+            Some(unlocked_ioctl_callback::<T>)
+        } else {
+            None
+        },
+        compat_ioctl: if T::TO_USE.compat_ioctl {
+            Some(compat_ioctl_callback::<T>)
+        } else {
+            None
+        },
+        poll: if T::TO_USE.poll {
+            Some(poll_callback::<T>)
+        } else {
+            None
+        },
+        mmap: if T::TO_USE.mmap {
+            Some(mmap_callback::<T>)
+        } else {
+            None
+        },
+        splice_read: if T::TO_USE.read_iter {
+            Some(bindings::generic_file_splice_read)
+        } else {
+            None
+        },
+        splice_write: if T::TO_USE.write_iter {
+            Some(bindings::iter_file_splice_write)
+        } else {
+            None
+        },
     };
 }
 
+/// The vtable type that `proc_create` expects on this kernel.
+#[cfg(kernel_5_6_0_or_greater)]
+pub(crate) type ProcVtable = bindings::proc_ops;
+
+#[cfg(not(kernel_5_6_0_or_greater))]
+pub(crate) type ProcVtable = bindings::file_operations;
+
+pub(crate) struct ProcOperationsVtable<A, T>(marker::PhantomData<A>, marker::PhantomData<T>);
+
+#[cfg(kernel_5_6_0_or_greater)]
+impl<A: OpenAdapter, T: FileOperations> ProcOperationsVtable<A, T> {
+    pub(crate) const VTABLE: ProcVtable = proc_vtable! {
+        open: Some(open_callback::<A, T>),
+        release: Some(release_callback::<T>),
+        read: if T::TO_USE.read {
+            Some(read_callback::<T>)
+        } else {
+            None
+        },
+        read_iter: if T::TO_USE.read_iter {
+            Some(read_iter_callback::<T>)
+        } else {
+            None
+        },
+        write: if T::TO_USE.write {
+            Some(write_callback::<T>)
+        } else {
+            None
+        },
+        llseek: if T::TO_USE.seek {
+            Some(llseek_callback::<T>)
+        } else {
+            None
+        },
+        ioctl: if T::TO_USE.ioctl {
+            Some(unlocked_ioctl_callback::<T>)
+        } else {
+            None
+        },
+        compat_ioctl: if T::TO_USE.compat_ioctl {
+            Some(compat_ioctl_callback::<T>)
+        } else {
+            None
+        },
+        poll: if T::TO_USE.poll {
+            Some(poll_callback::<T>)
+        } else {
+            None
+        },
+        mmap: if T::TO_USE.mmap {
+            Some(mmap_callback::<T>)
+        } else {
+            None
+        },
+    };
+}
+
+// Before 5.6, the procfs vtable of a `FileOperations` is its regular
+// `struct file_operations`, which keeps splice support.
+#[cfg(not(kernel_5_6_0_or_greater))]
+impl<A: OpenAdapter, T: FileOperations> ProcOperationsVtable<A, T> {
+    pub(crate) const VTABLE: ProcVtable = FileOperationsVtable::<A, T>::VTABLE;
+}
+
 /// `FileOperations` corresponds to the kernel's `struct file_operations`. You
 /// implement this trait whenever you'd create a `struct file_operations`.
+/// The same implementation can be registered as a character device, a misc
+/// device or a procfs entry, for which the crate builds a `struct proc_ops`
+/// on 5.6 and later.
 /// File descriptors may be used from multiple threads (or processes)
 /// concurrently, so your type must be `Sync`.
 pub trait FileOperations: Sync + Sized {
+    /// The state shared by every instance of this file, which is passed to
+    /// the registration (e.g. [`chrdev::Builder::register_device`] or
+    /// [`ProcDirEntry::create_data`]) and handed to
+    /// [`FileOperations::open`]. Use `()` if there is none.
+    ///
+    /// [`chrdev::Builder::register_device`]: crate::chrdev::Builder::register_device
+    /// [`ProcDirEntry::create_data`]: crate::procfs::ProcDirEntry::create_data
+    type Context: Send + Sync;
+
+    /// The optional callbacks this file implements. Only those are installed
+    /// in the vtable; use [`declare_file_operations!`] to define it.
+    ///
+    /// [`declare_file_operations!`]: crate::declare_file_operations
+    const TO_USE: ToUse;
+
     /// Creates a new instance of this file. Corresponds to the `open` function
     /// pointer in `struct file_operations`.
-    fn open() -> KernelResult<Self>;
+    fn open(context: &Arc<Self::Context>, inode: &Inode, file: &File) -> KernelResult<Self>;
 
     /// Reads data from this file to userspace. Corresponds to the `read`
     /// function pointer in `struct file_operations`.
@@ -234,6 +849,28 @@ pub trait FileOperations: Sync + Sized {
         Err(Error::EINVAL)
     }
 
+    /// Reads data from this file into `buf`, which may point to userspace or
+    /// kernel memory. Corresponds to the `read_iter` function pointer in
+    /// `struct file_operations`, which is used by `readv`, io_uring and
+    /// in-kernel readers.
+    ///
+    /// If this is not used, the kernel calls [`FileOperations::read`] once
+    /// for each userspace buffer instead.
+    fn read_iter(&self, _file: &File, _buf: &mut IovIter, _offset: u64) -> KernelResult<()> {
+        Err(Error::EINVAL)
+    }
+
+    /// Writes data from `buf`, which may point to userspace or kernel
+    /// memory, to this file. Corresponds to the `write_iter` function pointer
+    /// in `struct file_operations`, which is used by `writev`, io_uring and
+    /// in-kernel writers.
+    ///
+    /// If this is not used, the kernel calls [`FileOperations::write`] once
+    /// for each userspace buffer instead.
+    fn write_iter(&self, _file: &File, _buf: &mut IovIter, _offset: u64) -> KernelResult<()> {
+        Err(Error::EINVAL)
+    }
+
     /// Changes the position of the file. Corresponds to the `llseek` function
     /// pointer in `struct file_operations`.
     fn llseek(
@@ -245,7 +882,11 @@ pub trait FileOperations: Sync + Sized {
     }
 
     /// Corresponds to the `unlocked_ioctl` function pointer in
-    /// `struct file_operations`.
+    /// `struct file_operations`. Commands declared with
+    /// [`ioctl::Command`] can be handled with [`ioctl::Command::dispatch`].
+    ///
+    /// [`ioctl::Command`]: crate::ioctl::Command
+    /// [`ioctl::Command::dispatch`]: crate::ioctl::Command::dispatch
     fn unlocked_ioctl(
         &self,
         _file: &File,
@@ -254,4 +895,37 @@ pub trait FileOperations: Sync + Sized {
     ) -> KernelResult<u64> {
         Err(Error::EINVAL)
     }
+
+    /// Handles ioctls issued by 32-bit userspace on a 64-bit kernel.
+    /// Corresponds to the `compat_ioctl` function pointer in
+    /// `struct file_operations`.
+    ///
+    /// By default this converts `param` with `compat_ptr` and forwards it to
+    /// [`FileOperations::unlocked_ioctl`], like `compat_ptr_ioctl` in C.
+    /// That is correct as long as every argument is a pointer to a type with
+    /// the same layout for 32-bit and 64-bit userspace; `param` is passed
+    /// unconverted if this is overridden. If this is not used, 32-bit ioctls
+    /// fail with `ENOTTY`.
+    fn compat_ioctl(&self, file: &File, num: Ioctl, param: u64) -> KernelResult<u64> {
+        let param = unsafe { compat_ptr_helper(param as c_types::c_ulong) };
+        self.unlocked_ioctl(file, num, param as u64)
+    }
+
+    /// Registers the wait queues this file may become ready on with `table`
+    /// and returns the events that are currently ready. Corresponds to the
+    /// `poll` function pointer in `struct file_operations`.
+    ///
+    /// An error is reported to userspace as [`PollFlags::ERR`]. If this is
+    /// not used, the file is always readable and writable.
+    fn poll(&self, _file: &File, _table: &PollTable) -> KernelResult<PollFlags> {
+        Ok(PollFlags::IN | PollFlags::OUT | PollFlags::RDNORM | PollFlags::WRNORM)
+    }
+
+    /// Maps this file into the address range described by `vma`, typically
+    /// with [`VMA::remap_pfn_range`], [`VMA::insert_page`] or
+    /// [`VMA::set_operations`]. Corresponds to the `mmap` function pointer in
+    /// `struct file_operations`.
+    fn mmap(&self, _file: &File, _vma: &mut VMA) -> KernelResult<()> {
+        Err(Error::ENODEV)
+    }
 }
@@ -1,8 +1,16 @@
 #include <linux/bug.h>
+#include <linux/compat.h>
+#include <linux/debugfs.h>
+#include <linux/device.h>
+#include <linux/mm.h>
+#include <linux/poll.h>
 #include <linux/printk.h>
+#include <linux/proc_fs.h>
 #include <linux/uaccess.h>
 #include <linux/version.h>
 #include <linux/spinlock.h>
+#include <linux/uio.h>
+#include <linux/wait.h>
 
 #if LINUX_VERSION_CODE >= KERNEL_VERSION(5, 8, 0)
 #include <linux/pgtable.h>
@@ -12,6 +20,11 @@
 
 #include <asm/msr.h>
 
+/* pr_debug() records the module name in its dynamic debug descriptor. */
+#ifndef KBUILD_MODNAME
+#define KBUILD_MODNAME "rust"
+#endif
+
 void bug_helper(void)
 {
     BUG();
@@ -26,12 +39,79 @@ int access_ok_helper(const void __user *addr, unsigned long n)
 #endif
 }
 
+struct class *class_create_helper(const char *name)
+{
+	return class_create(THIS_MODULE, name);
+}
+
+void printk_debug_helper(const char *modname, int len, const char *s)
+{
+	pr_debug("%s: %.*s", modname, len, s);
+}
+
+void *pde_data_helper(const struct inode *inode)
+{
+#if LINUX_VERSION_CODE >= KERNEL_VERSION(5, 17, 0)
+	return pde_data(inode);
+#else
+	return PDE_DATA(inode);
+#endif
+}
+
+static int debugfs_create_result(struct dentry *dentry)
+{
+	if (IS_ERR(dentry))
+		return PTR_ERR(dentry);
+	/* Older kernels return NULL instead of an error pointer. */
+	return dentry ? 0 : -ENOMEM;
+}
+
+/*
+ * The debugfs_create_*() functions for plain values stopped returning their
+ * dentry when their failures were made silent, each in a different release.
+ * Evaluates to the error the call reports, or 0 if it reports none.
+ */
+#define debugfs_returns_void(call) __builtin_types_compatible_p(typeof(call), void)
+#define debugfs_create_call(call)					\
+	__builtin_choose_expr(debugfs_returns_void(call), ((call), 0),	\
+		debugfs_create_result(__builtin_choose_expr(		\
+			debugfs_returns_void(call), (struct dentry *)NULL, (call))))
+
+int debugfs_create_u32_helper(const char *name, umode_t mode,
+			      struct dentry *parent, u32 *value)
+{
+	return debugfs_create_call(debugfs_create_u32(name, mode, parent, value));
+}
+
+int debugfs_create_x32_helper(const char *name, umode_t mode,
+			      struct dentry *parent, u32 *value)
+{
+	return debugfs_create_call(debugfs_create_x32(name, mode, parent, value));
+}
+
+int debugfs_create_u64_helper(const char *name, umode_t mode,
+			      struct dentry *parent, u64 *value)
+{
+	return debugfs_create_call(debugfs_create_u64(name, mode, parent, value));
+}
+
+int debugfs_create_bool_helper(const char *name, umode_t mode,
+			       struct dentry *parent, bool *value)
+{
+	return debugfs_create_call(debugfs_create_bool(name, mode, parent, value));
+}
+
 struct task_struct *
 current_helper(void)
 {
 	return current;
 }
 
+void spin_lock_init_helper(spinlock_t *lock)
+{
+	spin_lock_init(lock);
+}
+
 void spin_lock_helper(spinlock_t *lock)
 {
 	spin_lock(lock);
@@ -42,6 +122,81 @@ void spin_unlock_helper(spinlock_t *lock)
 	spin_unlock(lock);
 }
 
+void init_waitqueue_head_helper(wait_queue_head_t *wq)
+{
+	init_waitqueue_head(wq);
+}
+
+void wake_up_interruptible_helper(wait_queue_head_t *wq)
+{
+	wake_up_interruptible(wq);
+}
+
+void wake_up_interruptible_all_helper(wait_queue_head_t *wq)
+{
+	wake_up_interruptible_all(wq);
+}
+
+int wait_event_interruptible_helper(wait_queue_head_t *wq,
+				    bool (*condition)(void *), void *data)
+{
+	return wait_event_interruptible(*wq, condition(data));
+}
+
+void poll_wait_helper(struct file *filp, wait_queue_head_t *wq, poll_table *p)
+{
+	poll_wait(filp, wq, p);
+}
+
+unsigned long compat_ptr_helper(unsigned long arg)
+{
+#ifdef CONFIG_COMPAT
+	return (unsigned long)compat_ptr(arg);
+#else
+	return arg;
+#endif
+}
+
+struct page *alloc_pages_helper(gfp_t gfp_mask, unsigned int order)
+{
+	return alloc_pages(gfp_mask, order);
+}
+
+void get_page_helper(struct page *page)
+{
+	get_page(page);
+}
+
+unsigned long page_to_pfn_helper(struct page *page)
+{
+	return page_to_pfn(page);
+}
+
+void *page_address_helper(struct page *page)
+{
+	return page_address(page);
+}
+
+pgprot_t pgprot_noncached_helper(pgprot_t prot)
+{
+	return pgprot_noncached(prot);
+}
+
+pgprot_t pgprot_writecombine_helper(pgprot_t prot)
+{
+	return pgprot_writecombine(prot);
+}
+
+size_t copy_to_iter_helper(const void *addr, size_t bytes, struct iov_iter *i)
+{
+	return copy_to_iter(addr, bytes, i);
+}
+
+size_t copy_from_iter_helper(void *addr, size_t bytes, struct iov_iter *i)
+{
+	return copy_from_iter(addr, bytes, i);
+}
+
 pgd_t *pgd_offset_helper(struct mm_struct *mm, unsigned long va)
 {
 	return pgd_offset(mm, va);
@@ -1,3 +1,10 @@
+use core::marker::PhantomData;
+use core::{mem, slice};
+
+use crate::c_types;
+use crate::error::{Error, KernelResult};
+use crate::user_ptr::UserSlicePtr;
+
 #[derive(Clone, Copy, Debug)]
 pub enum Ioctl {
     None(u32, u32, u32),
@@ -26,6 +33,8 @@ impl Ioctl {
     const SIZE_MASK: u32 = (1 << Ioctl::SIZE_BITS) - 1;
     const DIR_MASK: u32 = (1 << Ioctl::DIR_BITS) - 1;
 
+    const NONE: u32 = 0;
+
     pub fn size(self) -> u32 {
         match self {
             Ioctl::None(_, _, size) => size,
@@ -34,6 +43,30 @@ impl Ioctl {
             Ioctl::ReadWrite(_, _, size) => size,
         }
     }
+
+    /// Returns `raw` with the size field cleared, so that commands can be
+    /// matched by direction, type and number alone.
+    const fn without_size(raw: u32) -> u32 {
+        raw & !(Ioctl::SIZE_MASK << Ioctl::SIZE_SHIFT)
+    }
+
+    const fn encode(dir: u32, ty: u32, num: u32, size: u32) -> u32 {
+        (dir & Ioctl::DIR_MASK) << Ioctl::DIR_SHIFT
+            | (size & Ioctl::SIZE_MASK) << Ioctl::SIZE_SHIFT
+            | (ty & Ioctl::TYPE_MASK) << Ioctl::TYPE_SHIFT
+            | (num & Ioctl::NUM_MASK) << Ioctl::NUM_SHIFT
+    }
+}
+
+impl From<Ioctl> for u32 {
+    fn from(ioctl: Ioctl) -> Self {
+        match ioctl {
+            Ioctl::None(ty, num, size) => Ioctl::encode(Ioctl::NONE, ty, num, size),
+            Ioctl::Read(ty, num, size) => Ioctl::encode(Ioctl::READ, ty, num, size),
+            Ioctl::Write(ty, num, size) => Ioctl::encode(Ioctl::WRITE, ty, num, size),
+            Ioctl::ReadWrite(ty, num, size) => Ioctl::encode(Ioctl::READ_WRITE, ty, num, size),
+        }
+    }
 }
 
 impl From<u32> for Ioctl {
@@ -51,3 +84,137 @@ impl From<u32> for Ioctl {
         }
     }
 }
+
+/// Types that can be copied to and from userspace as the argument of a
+/// [`Command`].
+///
+/// # Safety
+///
+/// Implementors must not contain padding, and every bit pattern must be a
+/// valid value of the type, since the argument is filled in with whatever
+/// userspace passes.
+pub unsafe trait IoctlArg {}
+
+macro_rules! impl_ioctl_arg {
+    ($($t:ty),*) => {
+        $(unsafe impl IoctlArg for $t {})*
+    };
+}
+
+impl_ioctl_arg!((), u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+// Fails to evaluate, so a `const` calling it doesn't compile, unless
+// `condition` holds.
+const fn const_assert(condition: bool) {
+    [()][!condition as usize]
+}
+
+/// An ioctl command taking an argument of type `T`. The command number is
+/// computed at compile time, like the `_IO`, `_IOR`, `_IOW` and `_IOWR`
+/// macros in C. `ty` and `num` must fit in 8 bits and `T` must be smaller
+/// than 16 KiB, or declaring the command fails to compile. Example:
+/// ```rust,no_run
+/// use linux_kernel_module::ioctl::Command;
+///
+/// const GET_COUNT: Command<u64> = Command::ior(b'c' as u32, 0);
+/// const SET_COUNT: Command<u64> = Command::iow(b'c' as u32, 1);
+/// ```
+pub struct Command<T> {
+    raw: u32,
+    _phantom: PhantomData<T>,
+}
+
+impl Command<()> {
+    /// Declares a command without an argument. Corresponds to `_IO`.
+    pub const fn io(ty: u32, num: u32) -> Self {
+        Command::new(Ioctl::NONE, ty, num)
+    }
+}
+
+impl<T> Command<T> {
+    const fn new(dir: u32, ty: u32, num: u32) -> Self {
+        const_assert(ty <= Ioctl::TYPE_MASK);
+        const_assert(num <= Ioctl::NUM_MASK);
+        const_assert(mem::size_of::<T>() <= Ioctl::SIZE_MASK as usize);
+        Command {
+            raw: Ioctl::encode(dir, ty, num, mem::size_of::<T>() as u32),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Declares a command that copies a `T` to userspace. Corresponds to
+    /// `_IOR`.
+    pub const fn ior(ty: u32, num: u32) -> Self {
+        Command::new(Ioctl::READ, ty, num)
+    }
+
+    /// Declares a command that copies a `T` from userspace. Corresponds to
+    /// `_IOW`.
+    pub const fn iow(ty: u32, num: u32) -> Self {
+        Command::new(Ioctl::WRITE, ty, num)
+    }
+
+    /// Declares a command that copies a `T` from userspace and back.
+    /// Corresponds to `_IOWR`.
+    pub const fn iowr(ty: u32, num: u32) -> Self {
+        Command::new(Ioctl::READ_WRITE, ty, num)
+    }
+
+    /// Returns the raw command number.
+    pub const fn raw(&self) -> u32 {
+        self.raw
+    }
+}
+
+impl<T: IoctlArg> Command<T> {
+    /// Handles `num` if it is this command, and returns `None` otherwise.
+    /// Commands are matched by direction, type and number.
+    ///
+    /// For `_IOW` and `_IOWR` commands the argument is first copied from the
+    /// userspace pointer `param`; for `_IOR` commands it starts out zeroed.
+    /// `f` may then inspect and modify it, after which it is copied back to
+    /// `param` for `_IOR` and `_IOWR` commands. The result of `f` is returned
+    /// to userspace.
+    ///
+    /// Returns `EINVAL` if the size encoded in the command does not match the
+    /// size of `T`.
+    pub fn dispatch<F>(&self, num: Ioctl, param: u64, f: F) -> Option<KernelResult<u64>>
+    where
+        F: FnOnce(&mut T) -> KernelResult<u64>,
+    {
+        if Ioctl::without_size(u32::from(num)) != Ioctl::without_size(self.raw) {
+            return None;
+        }
+
+        Some(self.handle(num, param, f))
+    }
+
+    fn handle<F>(&self, num: Ioctl, param: u64, f: F) -> KernelResult<u64>
+    where
+        F: FnOnce(&mut T) -> KernelResult<u64>,
+    {
+        let size = mem::size_of::<T>();
+        if num.size() as usize != size {
+            return Err(Error::EINVAL);
+        }
+
+        // `T: IoctlArg` is valid for any bit pattern, including all zeroes.
+        let mut value: T = unsafe { mem::zeroed() };
+
+        if let Ioctl::Write(..) | Ioctl::ReadWrite(..) = num {
+            let data = unsafe { slice::from_raw_parts_mut(&mut value as *mut T as *mut u8, size) };
+            unsafe { UserSlicePtr::new(param as *mut c_types::c_void, size)? }
+                .reader()
+                .read(data)?;
+        }
+
+        let ret = f(&mut value)?;
+
+        if let Ioctl::Read(..) | Ioctl::ReadWrite(..) = num {
+            let data = unsafe { slice::from_raw_parts(&value as *const T as *const u8, size) };
+            unsafe { UserSlicePtr::new(param as *mut c_types::c_void, size)? }.write_all(data)?;
+        }
+
+        Ok(ret)
+    }
+}
@@ -0,0 +1,99 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::bindings;
+use crate::c_types;
+use crate::error::{Error, KernelResult};
+
+extern "C" {
+    fn copy_to_iter_helper(
+        addr: *const c_types::c_void,
+        bytes: usize,
+        i: *mut bindings::iov_iter,
+    ) -> usize;
+    fn copy_from_iter_helper(
+        addr: *mut c_types::c_void,
+        bytes: usize,
+        i: *mut bindings::iov_iter,
+    ) -> usize;
+}
+
+/// A cursor over a list of buffers, which may live in userspace (`iovec`),
+/// in the kernel (`kvec`) or in pages (`bvec`). Corresponds to the kernel's
+/// `struct iov_iter`.
+///
+/// Like [`UserSlicePtrReader`] and [`UserSlicePtrWriter`], an `IovIter` only
+/// moves forward: every byte is read or written at most once.
+///
+/// [`UserSlicePtrReader`]: crate::user_ptr::UserSlicePtrReader
+/// [`UserSlicePtrWriter`]: crate::user_ptr::UserSlicePtrWriter
+pub struct IovIter {
+    ptr: *mut bindings::iov_iter,
+}
+
+impl IovIter {
+    pub(crate) unsafe fn from_ptr(ptr: *mut bindings::iov_iter) -> IovIter {
+        IovIter { ptr }
+    }
+
+    /// Returns the number of bytes left in this iterator.
+    pub fn len(&self) -> usize {
+        unsafe { (*self.ptr).count }
+    }
+
+    /// Returns `true` if `self.len()` is 0.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read all data remaining in the iterator and return it in a `Vec`.
+    ///
+    /// Returns EFAULT if the buffers could not be read.
+    pub fn read_all(&mut self) -> KernelResult<Vec<u8>> {
+        let mut data = vec![0; self.len()];
+        self.read(&mut data)?;
+        Ok(data)
+    }
+
+    /// Fills `data` from the iterator.
+    ///
+    /// Returns EFAULT if `data` is larger than the remaining buffers (in which
+    /// case nothing is read), or if the buffers could not be read.
+    pub fn read(&mut self, data: &mut [u8]) -> KernelResult<()> {
+        if data.len() > self.len() {
+            return Err(Error::EFAULT);
+        }
+        let res = unsafe {
+            copy_from_iter_helper(
+                data.as_mut_ptr() as *mut c_types::c_void,
+                data.len(),
+                self.ptr,
+            )
+        };
+        if res != data.len() {
+            return Err(Error::EFAULT);
+        }
+        Ok(())
+    }
+
+    /// Writes `data` into the iterator.
+    ///
+    /// Returns EFAULT if `data` is larger than the remaining buffers (in which
+    /// case nothing is written), or if the buffers could not be written.
+    pub fn write(&mut self, data: &[u8]) -> KernelResult<()> {
+        if data.len() > self.len() {
+            return Err(Error::EFAULT);
+        }
+        let res = unsafe {
+            copy_to_iter_helper(
+                data.as_ptr() as *const c_types::c_void,
+                data.len(),
+                self.ptr,
+            )
+        };
+        if res != data.len() {
+            return Err(Error::EFAULT);
+        }
+        Ok(())
+    }
+}
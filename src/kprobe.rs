@@ -53,6 +53,8 @@ unsafe extern "C" fn return_callback<T: ReturnProbeHandler>(
 struct ReturnProbeStorage<T: ReturnProbeHandler> {
     handler: Box<T>,
     probe: bindings::kretprobe,
+    // The probe keeps a pointer to the symbol name.
+    _symbol: types::CString,
 }
 
 pub struct ReturnProbe<T: ReturnProbeHandler> {
@@ -63,10 +65,11 @@ unsafe impl<T: ReturnProbeHandler> Sync for ReturnProbe<T> {}
 
 impl<T: ReturnProbeHandler> ReturnProbe<T> {
     pub fn register(
-        symbol: types::CStr<'static>,
+        symbol: types::CStr,
         max_active: i32,
         handler: T,
     ) -> KernelResult<ReturnProbe<T>> {
+        let symbol = types::CString::from_cstr(symbol)?;
         let probe = bindings::kretprobe {
             kp: bindings::kprobe {
                 symbol_name: symbol.as_ptr() as *const i8,
@@ -80,6 +83,7 @@ impl<T: ReturnProbeHandler> ReturnProbe<T> {
         let mut storage = vec![ReturnProbeStorage {
             handler,
             probe,
+            _symbol: symbol,
         }].into_boxed_slice();
 
         let result = unsafe {
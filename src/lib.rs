@@ -1,5 +1,11 @@
 #![no_std]
-#![feature(allocator_api, alloc_error_handler, asm, stmt_expr_attributes)]
+#![feature(
+    allocator_api,
+    alloc_error_handler,
+    asm,
+    stmt_expr_attributes,
+    try_reserve
+)]
 
 extern crate alloc;
 
@@ -10,12 +16,16 @@ mod allocator;
 pub mod bindings;
 pub mod c_types;
 pub mod chrdev;
+pub mod debugfs;
 pub mod device;
 mod error;
+#[macro_use]
 pub mod file_operations;
 pub mod filesystem;
 pub mod ioctl;
+pub mod iov_iter;
 pub mod kprobe;
+pub mod miscdev;
 #[cfg(target_arch = "x86_64")]
 pub mod msr;
 pub mod page;
@@ -23,10 +33,10 @@ pub mod paging;
 pub mod percpu;
 pub mod printk;
 pub mod procfs;
-pub mod proc_operations;
 #[cfg(kernel_4_13_0_or_greater)]
 pub mod random;
 pub mod rwlock;
+pub mod seq_file;
 pub mod spinlock;
 pub mod sysctl;
 pub mod task;
@@ -36,9 +46,10 @@ pub mod usb;
 pub mod usb_net;
 pub mod user_ptr;
 pub mod vma;
+pub mod wait_queue;
 
-pub use crate::error::{Error, KernelResult};
-pub use crate::types::{CStr, Mode};
+pub use crate::error::{from_err_ptr, Error, KernelResult};
+pub use crate::types::{CStr, CString, Mode};
 
 /// Declares the entrypoint for a kernel module. The first argument should be a type which
 /// implements the [`KernelModule`] trait. Also accepts various forms of kernel metadata.
@@ -0,0 +1,88 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::{marker, mem};
+
+use intrusive_collections::container_of;
+
+use crate::bindings;
+use crate::c_types;
+use crate::device::DeviceNumber;
+use crate::error::{Error, KernelResult};
+use crate::file_operations::{FileOperations, FileOperationsVtable, OpenAdapter};
+use crate::types::{CStr, CString, Mode};
+
+struct MiscDevice<T: FileOperations> {
+    misc: bindings::miscdevice,
+    fops: bindings::file_operations,
+    name: CString,
+    context: Arc<T::Context>,
+}
+
+struct MiscAdapter<T>(marker::PhantomData<T>);
+
+impl<T: FileOperations> OpenAdapter for MiscAdapter<T> {
+    unsafe fn context(
+        _inode: *mut bindings::inode,
+        file: *mut bindings::file,
+    ) -> *const c_types::c_void {
+        // misc_open() points private_data at the miscdevice being opened.
+        let dev: *const MiscDevice<T> = container_of!(
+            (*file).private_data as *const bindings::miscdevice,
+            MiscDevice<T>,
+            misc
+        );
+        Arc::as_ptr(&(*dev).context) as *const c_types::c_void
+    }
+}
+
+/// A single character device node registered with `misc_register`, which
+/// takes care of allocating a minor number and creating the `/dev` node.
+pub struct Registration<T: FileOperations> {
+    inner: Box<MiscDevice<T>>,
+}
+
+// This is safe because Registration doesn't actually expose any methods
+// that access the device.
+unsafe impl<T: FileOperations> Sync for Registration<T> {}
+
+impl<T: FileOperations> Registration<T> {
+    /// Registers `T` as the misc device `/dev/<name>`, with a dynamically
+    /// allocated minor number. Every file opened through the device receives
+    /// `context` in [`FileOperations::open`].
+    pub fn register(name: CStr, mode: Mode, context: Arc<T::Context>) -> KernelResult<Self> {
+        let mut inner = Box::new(MiscDevice::<T> {
+            misc: unsafe { mem::zeroed() },
+            fops: FileOperationsVtable::<MiscAdapter<T>, T>::VTABLE,
+            name: CString::from_cstr(name)?,
+            context,
+        });
+
+        // misc devices have no cdev of their own to pin the module, so the
+        // file operations have to.
+        inner.fops.owner = unsafe { &mut bindings::__this_module };
+        inner.misc.minor = bindings::MISC_DYNAMIC_MINOR as c_types::c_int;
+        inner.misc.name = inner.name.as_ptr() as *const c_types::c_char;
+        inner.misc.fops = &inner.fops;
+        inner.misc.mode = mode.as_int();
+
+        let res = unsafe { bindings::misc_register(&mut inner.misc) };
+        if res != 0 {
+            return Err(Error::from_kernel_errno(res));
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// Returns the device number that was allocated for this device.
+    pub fn dev(&self) -> DeviceNumber {
+        unsafe { DeviceNumber::from((*self.inner.misc.this_device).devt) }
+    }
+}
+
+impl<T: FileOperations> Drop for Registration<T> {
+    fn drop(&mut self) {
+        unsafe {
+            bindings::misc_deregister(&mut self.inner.misc);
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use core::ptr;
+
 use crate::bindings;
 use crate::c_types;
+use crate::error::{Error, KernelResult};
 
 #[allow(non_upper_case_globals)]
 const __START_KERNEL_map: u64 = 0xffffffff80000000;
@@ -11,3 +14,97 @@ pub fn virt_to_phys(x: c_types::c_ulong) -> c_types::c_ulong {
         unsafe { bindings::phys_base + x - __START_KERNEL_map }
     }
 }
+
+extern "C" {
+    fn alloc_pages_helper(gfp_mask: bindings::gfp_t, order: c_types::c_uint)
+        -> *mut bindings::page;
+    fn get_page_helper(page: *mut bindings::page);
+    fn page_to_pfn_helper(page: *mut bindings::page) -> c_types::c_ulong;
+    fn page_address_helper(page: *mut bindings::page) -> *mut c_types::c_void;
+}
+
+/// A single, zero-initialized page of physical memory owned by the module.
+/// Typically used to back memory that is shared with userspace through
+/// [`VMA::insert_page`].
+///
+/// [`VMA::insert_page`]: crate::vma::VMA::insert_page
+pub struct Page {
+    raw: *mut bindings::page,
+}
+
+// This is safe because the page is only ever accessed through the methods
+// below, which copy to and from it.
+unsafe impl Send for Page {}
+unsafe impl Sync for Page {}
+
+impl Page {
+    pub const SIZE: usize = bindings::PAGE_SIZE;
+
+    pub fn alloc() -> KernelResult<Self> {
+        let raw = unsafe { alloc_pages_helper(bindings::GFP_KERNEL | bindings::__GFP_ZERO, 0) };
+
+        if raw.is_null() {
+            return Err(Error::ENOMEM);
+        }
+
+        Ok(Self { raw })
+    }
+
+    pub fn raw(&self) -> *mut bindings::page {
+        self.raw
+    }
+
+    /// Returns the page frame number of this page.
+    pub fn pfn(&self) -> u64 {
+        unsafe { page_to_pfn_helper(self.raw) }
+    }
+
+    /// Takes an additional reference on this page on behalf of the kernel.
+    pub(crate) fn get(&self) {
+        unsafe {
+            get_page_helper(self.raw);
+        }
+    }
+
+    /// Copies `data` into the page at `offset`.
+    pub fn write(&self, offset: usize, data: &[u8]) -> KernelResult<()> {
+        if offset
+            .checked_add(data.len())
+            .map_or(true, |end| end > Self::SIZE)
+        {
+            return Err(Error::EINVAL);
+        }
+
+        unsafe {
+            let ptr = (page_address_helper(self.raw) as *mut u8).add(offset);
+            ptr::copy(data.as_ptr(), ptr, data.len());
+        }
+
+        Ok(())
+    }
+
+    /// Copies the contents of the page at `offset` into `data`.
+    pub fn read(&self, offset: usize, data: &mut [u8]) -> KernelResult<()> {
+        if offset
+            .checked_add(data.len())
+            .map_or(true, |end| end > Self::SIZE)
+        {
+            return Err(Error::EINVAL);
+        }
+
+        unsafe {
+            let ptr = (page_address_helper(self.raw) as *const u8).add(offset);
+            ptr::copy(ptr, data.as_mut_ptr(), data.len());
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Page {
+    fn drop(&mut self) {
+        unsafe {
+            bindings::__free_pages(self.raw, 0);
+        }
+    }
+}
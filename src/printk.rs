@@ -1,53 +1,356 @@
 use core::cmp;
-use core::fmt;
+use core::fmt::{self, Write};
+use core::ptr;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 
 use crate::bindings;
-use crate::c_types::c_int;
+use crate::c_types::{c_char, c_int, c_uint};
 
+extern "C" {
+    fn printk_debug_helper(modname: *const c_char, len: c_int, s: *const u8);
+}
+
+/// The severity of a kernel log message.
+#[derive(Clone, Copy)]
+pub enum Level {
+    Emerg,
+    Alert,
+    Crit,
+    Err,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+// Returns the name of the module this crate is linked into, as recorded by
+// modpost.
+fn module_name() -> *const c_char {
+    unsafe { bindings::__this_module.name.as_ptr() }
+}
+
+fn kern_level(level: Level) -> &'static [u8] {
+    match level {
+        Level::Emerg => bindings::KERN_EMERG,
+        Level::Alert => bindings::KERN_ALERT,
+        Level::Crit => bindings::KERN_CRIT,
+        Level::Err => bindings::KERN_ERR,
+        Level::Warning => bindings::KERN_WARNING,
+        Level::Notice => bindings::KERN_NOTICE,
+        Level::Info => bindings::KERN_INFO,
+        Level::Debug => bindings::KERN_DEBUG,
+    }
+}
+
+// Builds a printk format string from a KERN_* prefix and `fmt`, which must
+// be NUL-terminated and fit in the buffer.
+fn prefixed_format(prefix: &[u8], fmt: &[u8]) -> [u8; 24] {
+    // Don't copy the trailing NUL of the prefix.
+    let prefix_len = prefix.len() - 1;
+    let mut fmt_str = [0; 24];
+    fmt_str[..prefix_len].copy_from_slice(&prefix[..prefix_len]);
+    fmt_str[prefix_len..prefix_len + fmt.len()].copy_from_slice(fmt);
+    fmt_str
+}
+
+/// The per-callsite state of the `pr_*_ratelimited!` macros. Allows a burst
+/// of `DEFAULT_RATELIMIT_BURST` messages every `DEFAULT_RATELIMIT_INTERVAL`,
+/// like `printk_ratelimited` in C.
 #[doc(hidden)]
-pub fn printk(s: &[u8]) {
-    // Don't copy the trailing NUL from `KERN_INFO`.
-    let mut fmt_str = [0; bindings::KERN_INFO.len() - 1 + b"%.*s\0".len()];
-    fmt_str[..bindings::KERN_INFO.len() - 1]
-        .copy_from_slice(&bindings::KERN_INFO[..bindings::KERN_INFO.len() - 1]);
-    fmt_str[bindings::KERN_INFO.len() - 1..].copy_from_slice(b"%.*s\0");
+pub struct RatelimitState {
+    callsite: &'static str,
+    begin: AtomicUsize,
+    printed: AtomicU32,
+    missed: AtomicU32,
+}
+
+impl RatelimitState {
+    pub const fn new(callsite: &'static str) -> RatelimitState {
+        RatelimitState {
+            callsite,
+            begin: AtomicUsize::new(0),
+            printed: AtomicU32::new(0),
+            missed: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns whether a message may be printed now. Corresponds to
+    /// `__ratelimit`.
+    pub fn check(&self) -> bool {
+        let now = unsafe { ptr::read_volatile(&bindings::jiffies) } as usize;
+        let begin = self.begin.load(Ordering::Relaxed);
+
+        if begin == 0 {
+            let _ = self
+                .begin
+                .compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed);
+        } else if now.wrapping_sub(begin) >= bindings::DEFAULT_RATELIMIT_INTERVAL as usize
+            && self
+                .begin
+                .compare_exchange(begin, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            // Only the caller that starts the new interval reports what was
+            // suppressed during the previous one.
+            let missed = self.missed.swap(0, Ordering::Relaxed);
+            if missed > 0 {
+                crate::pr_warn!("{}: {} callbacks suppressed", self.callsite, missed);
+            }
+            self.printed.store(0, Ordering::Relaxed);
+        }
+
+        if self.printed.fetch_add(1, Ordering::Relaxed) < bindings::DEFAULT_RATELIMIT_BURST {
+            true
+        } else {
+            self.missed.fetch_add(1, Ordering::Relaxed);
+            false
+        }
+    }
+}
+
+/// How each line of [`print_hex_dump`] is prefixed, after the prefix
+/// string.
+#[derive(Clone, Copy)]
+pub enum DumpPrefix {
+    /// No prefix.
+    None,
+    /// The (hashed) address of the line's first byte.
+    Address,
+    /// The offset of the line's first byte in the buffer.
+    Offset,
+}
+
+// The longest line `hex_dump_line` produces: 32 bytes in groups of 1, padded
+// to the ASCII column, followed by 32 characters.
+const HEX_LINE_MAX: usize = 32 * 4 + 1;
+
+struct HexLine {
+    data: [u8; HEX_LINE_MAX],
+    pos: usize,
+}
+
+impl fmt::Write for HexLine {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let copy_len = cmp::min(HEX_LINE_MAX - self.pos, s.len());
+        self.data[self.pos..self.pos + copy_len].copy_from_slice(&s.as_bytes()[..copy_len]);
+        self.pos += copy_len;
+        Ok(())
+    }
+}
+
+// Formats up to `rowsize` bytes of `buf` the way `hex_dump_to_buffer` does.
+fn hex_dump_line(
+    writer: &mut HexLine,
+    buf: &[u8],
+    rowsize: usize,
+    mut groupsize: usize,
+    ascii: bool,
+) -> fmt::Result {
+    let len = cmp::min(buf.len(), rowsize);
+    if !groupsize.is_power_of_two() || groupsize > 8 || len % groupsize != 0 {
+        groupsize = 1;
+    }
+
+    for (j, group) in buf[..len].chunks(groupsize).enumerate() {
+        if j != 0 {
+            writer.write_str(" ")?;
+        }
+        // Groups are printed as native-endian integers.
+        let mut bytes = [0; 8];
+        bytes[..groupsize].copy_from_slice(group);
+        let value = u64::from_ne_bytes(bytes);
+        write!(writer, "{:0width$x}", value, width = groupsize * 2)?;
+    }
 
-    // TODO: I believe printk never fails
-    unsafe { bindings::printk(fmt_str.as_ptr() as _, s.len() as c_int, s.as_ptr()) };
+    if ascii {
+        let ascii_column = rowsize * 2 + rowsize / groupsize + 1;
+        while writer.pos < ascii_column {
+            writer.write_str(" ")?;
+        }
+        for &c in &buf[..len] {
+            let c = if c.is_ascii_graphic() || c == b' ' {
+                c
+            } else {
+                b'.'
+            };
+            writer.write_char(c as char)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints `buf` as a hex dump at `level`, with `rowsize` bytes per line
+/// (16 or 32) shown in groups of `groupsize` bytes (1, 2, 4 or 8), and
+/// optionally followed by their ASCII representation. Each line starts with
+/// `prefix` and the offset or address selected by `prefix_type`. Follows
+/// the formatting of `print_hex_dump`; unlike [`pr_debug!`], messages at
+/// [`Level::Debug`] are always printed.
+pub fn print_hex_dump(
+    level: Level,
+    prefix: &str,
+    prefix_type: DumpPrefix,
+    mut rowsize: usize,
+    groupsize: usize,
+    buf: &[u8],
+    ascii: bool,
+) {
+    if rowsize != 16 && rowsize != 32 {
+        rowsize = 16;
+    }
+
+    for (i, row) in buf.chunks(rowsize).enumerate() {
+        let offset = i * rowsize;
+        let mut line = HexLine {
+            data: [0; HEX_LINE_MAX],
+            pos: 0,
+        };
+        let _ = hex_dump_line(&mut line, row, rowsize, groupsize, ascii);
+        let line = &line.data[..line.pos];
+
+        unsafe {
+            match prefix_type {
+                DumpPrefix::Address => bindings::printk(
+                    prefixed_format(kern_level(level), b"%.*s%p: %.*s\n\0").as_ptr() as _,
+                    prefix.len() as c_int,
+                    prefix.as_ptr(),
+                    row.as_ptr(),
+                    line.len() as c_int,
+                    line.as_ptr(),
+                ),
+                DumpPrefix::Offset => bindings::printk(
+                    prefixed_format(kern_level(level), b"%.*s%.8x: %.*s\n\0").as_ptr() as _,
+                    prefix.len() as c_int,
+                    prefix.as_ptr(),
+                    offset as c_uint,
+                    line.len() as c_int,
+                    line.as_ptr(),
+                ),
+                DumpPrefix::None => bindings::printk(
+                    prefixed_format(kern_level(level), b"%.*s%.*s\n\0").as_ptr() as _,
+                    prefix.len() as c_int,
+                    prefix.as_ptr(),
+                    line.len() as c_int,
+                    line.as_ptr(),
+                ),
+            };
+        }
+    }
 }
 
-// From kernel/print/printk.c
-const LOG_LINE_MAX: usize = 1024 - 32;
+// The size of the chunks in which formatted messages are passed to printk.
+// Small enough to be kept on the stack.
+const CHUNK_MAX: usize = 128;
 
+/// Formats a message and passes it to printk in chunks, so that messages of
+/// any length can be printed with bounded stack usage. Each line becomes a
+/// separate log record, whose first chunk carries the log level and whose
+/// following chunks are appended with `KERN_CONT`. Whatever is left is
+/// printed when the writer is dropped.
 #[doc(hidden)]
 pub struct LogLineWriter {
-    data: [u8; LOG_LINE_MAX],
+    data: [u8; CHUNK_MAX],
     pos: usize,
+    // `None` for `println!`, which prints at `KERN_INFO` without a prefix.
+    level: Option<Level>,
+    continued: bool,
 }
 
 #[allow(clippy::new_without_default)]
 impl LogLineWriter {
     pub fn new() -> LogLineWriter {
         LogLineWriter {
-            data: [0u8; LOG_LINE_MAX],
+            data: [0u8; CHUNK_MAX],
+            pos: 0,
+            level: None,
+            continued: false,
+        }
+    }
+
+    /// Creates a writer that prints at `level`, prefixed with the name of
+    /// the module. Messages at [`Level::Debug`] go through `pr_debug`, so
+    /// they are only printed if enabled through dynamic debug (or if the
+    /// kernel was built with `DEBUG`), all at once as described for
+    /// [`pr_debug!`]. Dynamic debug cannot continue a record, so every
+    /// chunk of them is printed as its own record.
+    pub fn with_level(level: Level) -> LogLineWriter {
+        LogLineWriter {
+            data: [0u8; CHUNK_MAX],
             pos: 0,
+            level: Some(level),
+            continued: false,
         }
     }
 
-    pub fn as_bytes(&self) -> &[u8] {
-        &self.data[..self.pos]
+    fn flush(&mut self) {
+        if self.pos == 0 {
+            return;
+        }
+        let len = self.pos as c_int;
+        let s = self.data.as_ptr();
+        // TODO: I believe printk never fails
+        unsafe {
+            match self.level {
+                Some(Level::Debug) => printk_debug_helper(module_name(), len, s),
+                _ if self.continued => {
+                    bindings::printk(
+                        prefixed_format(bindings::KERN_CONT, b"%.*s\0").as_ptr() as _,
+                        len,
+                        s,
+                    );
+                }
+                None => {
+                    bindings::printk(
+                        prefixed_format(bindings::KERN_INFO, b"%.*s\0").as_ptr() as _,
+                        len,
+                        s,
+                    );
+                }
+                Some(level) => {
+                    bindings::printk(
+                        prefixed_format(kern_level(level), b"%s: %.*s\0").as_ptr() as _,
+                        module_name(),
+                        len,
+                        s,
+                    );
+                }
+            }
+        }
+        self.pos = 0;
     }
 }
 
 impl fmt::Write for LogLineWriter {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        let copy_len = cmp::min(LOG_LINE_MAX - self.pos, s.as_bytes().len());
-        self.data[self.pos..self.pos + copy_len].copy_from_slice(&s.as_bytes()[..copy_len]);
-        self.pos += copy_len;
+        let mut bytes = s.as_bytes();
+        while !bytes.is_empty() {
+            let mut copy_len = cmp::min(CHUNK_MAX - self.pos, bytes.len());
+            let newline = match bytes[..copy_len].iter().position(|&c| c == b'\n') {
+                Some(i) => {
+                    copy_len = i + 1;
+                    true
+                }
+                None => false,
+            };
+            self.data[self.pos..self.pos + copy_len].copy_from_slice(&bytes[..copy_len]);
+            self.pos += copy_len;
+            bytes = &bytes[copy_len..];
+
+            if newline || self.pos == CHUNK_MAX {
+                self.flush();
+                self.continued = !newline;
+            }
+        }
         Ok(())
     }
 }
 
+impl Drop for LogLineWriter {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
 /// [`println!`] functions the same as it does in `std`, except instead of
 /// printing to `stdout`, it writes to the kernel console at the `KERN_INFO`
 /// level.
@@ -56,15 +359,213 @@ impl fmt::Write for LogLineWriter {
 #[macro_export]
 macro_rules! println {
     () => ({
-        $crate::printk::printk("\n".as_bytes());
+        $crate::println!("");
     });
     ($fmt:expr) => ({
-        $crate::printk::printk(concat!($fmt, "\n").as_bytes());
+        use ::core::fmt::Write;
+        let mut writer = $crate::printk::LogLineWriter::new();
+        let _ = writer.write_str(concat!($fmt, "\n"));
     });
     ($fmt:expr, $($arg:tt)*) => ({
         use ::core::fmt;
         let mut writer = $crate::printk::LogLineWriter::new();
-        let _ = fmt::write(&mut writer, format_args!(concat!($fmt, "\n"), $($arg)*)).unwrap();
-        $crate::printk::printk(writer.as_bytes());
+        let _ = fmt::write(&mut writer, format_args!(concat!($fmt, "\n"), $($arg)*));
+    });
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pr {
+    ($level:expr, $fmt:expr) => ({
+        use ::core::fmt::Write;
+        let mut writer = $crate::printk::LogLineWriter::with_level($level);
+        let _ = writer.write_str(concat!($fmt, "\n"));
+    });
+    ($level:expr, $fmt:expr, $($arg:tt)*) => ({
+        use ::core::fmt;
+        let mut writer = $crate::printk::LogLineWriter::with_level($level);
+        let _ = fmt::write(&mut writer, format_args!(concat!($fmt, "\n"), $($arg)*));
+    });
+}
+
+/// Prints an emergency message, like `pr_emerg` in C. The message is
+/// prefixed with the module name and followed by a newline.
+#[macro_export]
+macro_rules! pr_emerg {
+    ($($arg:tt)*) => ($crate::__pr!($crate::printk::Level::Emerg, $($arg)*));
+}
+
+/// Prints an alert, like `pr_alert` in C. See [`pr_emerg!`].
+#[macro_export]
+macro_rules! pr_alert {
+    ($($arg:tt)*) => ($crate::__pr!($crate::printk::Level::Alert, $($arg)*));
+}
+
+/// Prints a critical message, like `pr_crit` in C. See [`pr_emerg!`].
+#[macro_export]
+macro_rules! pr_crit {
+    ($($arg:tt)*) => ($crate::__pr!($crate::printk::Level::Crit, $($arg)*));
+}
+
+/// Prints an error, like `pr_err` in C. See [`pr_emerg!`].
+#[macro_export]
+macro_rules! pr_err {
+    ($($arg:tt)*) => ($crate::__pr!($crate::printk::Level::Err, $($arg)*));
+}
+
+/// Prints a warning, like `pr_warn` in C. See [`pr_emerg!`].
+#[macro_export]
+macro_rules! pr_warn {
+    ($($arg:tt)*) => ($crate::__pr!($crate::printk::Level::Warning, $($arg)*));
+}
+
+/// Prints a notice, like `pr_notice` in C. See [`pr_emerg!`].
+#[macro_export]
+macro_rules! pr_notice {
+    ($($arg:tt)*) => ($crate::__pr!($crate::printk::Level::Notice, $($arg)*));
+}
+
+/// Prints an informational message, like `pr_info` in C. See [`pr_emerg!`].
+#[macro_export]
+macro_rules! pr_info {
+    ($($arg:tt)*) => ($crate::__pr!($crate::printk::Level::Info, $($arg)*));
+}
+
+/// Prints a debug message, like `pr_debug` in C. See [`pr_emerg!`].
+///
+/// Debug messages are not printed unless enabled at runtime through dynamic
+/// debug, e.g. with
+/// `echo 'module <name> +p' > /sys/kernel/debug/dynamic_debug/control`.
+///
+/// Unlike in C, all the `pr_debug!` callsites of a module share a single
+/// dynamic debug descriptor, the one of the `pr_debug` in the crate's C
+/// helper. They can only be enabled together, per module, and queries by
+/// file or line do not match them.
+#[macro_export]
+macro_rules! pr_debug {
+    ($($arg:tt)*) => ($crate::__pr!($crate::printk::Level::Debug, $($arg)*));
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pr_once {
+    ($level:expr, $($arg:tt)*) => ({
+        static PRINTED: ::core::sync::atomic::AtomicBool =
+            ::core::sync::atomic::AtomicBool::new(false);
+        if !PRINTED.swap(true, ::core::sync::atomic::Ordering::Relaxed) {
+            $crate::__pr!($level, $($arg)*);
+        }
+    });
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pr_ratelimited {
+    ($level:expr, $($arg:tt)*) => ({
+        static STATE: $crate::printk::RatelimitState =
+            $crate::printk::RatelimitState::new(concat!(module_path!(), ":", line!()));
+        if STATE.check() {
+            $crate::__pr!($level, $($arg)*);
+        }
     });
 }
+
+/// Like [`pr_emerg!`], but only prints the first time this callsite is
+/// reached. The same goes for the other `pr_*_once!` macros.
+#[macro_export]
+macro_rules! pr_emerg_once {
+    ($($arg:tt)*) => ($crate::__pr_once!($crate::printk::Level::Emerg, $($arg)*));
+}
+
+/// Like [`pr_alert!`], but only prints once. See [`pr_emerg_once!`].
+#[macro_export]
+macro_rules! pr_alert_once {
+    ($($arg:tt)*) => ($crate::__pr_once!($crate::printk::Level::Alert, $($arg)*));
+}
+
+/// Like [`pr_crit!`], but only prints once. See [`pr_emerg_once!`].
+#[macro_export]
+macro_rules! pr_crit_once {
+    ($($arg:tt)*) => ($crate::__pr_once!($crate::printk::Level::Crit, $($arg)*));
+}
+
+/// Like [`pr_err!`], but only prints once. See [`pr_emerg_once!`].
+#[macro_export]
+macro_rules! pr_err_once {
+    ($($arg:tt)*) => ($crate::__pr_once!($crate::printk::Level::Err, $($arg)*));
+}
+
+/// Like [`pr_warn!`], but only prints once. See [`pr_emerg_once!`].
+#[macro_export]
+macro_rules! pr_warn_once {
+    ($($arg:tt)*) => ($crate::__pr_once!($crate::printk::Level::Warning, $($arg)*));
+}
+
+/// Like [`pr_notice!`], but only prints once. See [`pr_emerg_once!`].
+#[macro_export]
+macro_rules! pr_notice_once {
+    ($($arg:tt)*) => ($crate::__pr_once!($crate::printk::Level::Notice, $($arg)*));
+}
+
+/// Like [`pr_info!`], but only prints once. See [`pr_emerg_once!`].
+#[macro_export]
+macro_rules! pr_info_once {
+    ($($arg:tt)*) => ($crate::__pr_once!($crate::printk::Level::Info, $($arg)*));
+}
+
+/// Like [`pr_debug!`], but only prints once. See [`pr_emerg_once!`].
+#[macro_export]
+macro_rules! pr_debug_once {
+    ($($arg:tt)*) => ($crate::__pr_once!($crate::printk::Level::Debug, $($arg)*));
+}
+
+/// Like [`pr_emerg!`], but drops messages when this callsite prints too
+/// often, like `pr_emerg_ratelimited` in C. A burst of messages is allowed
+/// every few seconds, after which the number of suppressed messages is
+/// reported. The same goes for the other `pr_*_ratelimited!` macros.
+#[macro_export]
+macro_rules! pr_emerg_ratelimited {
+    ($($arg:tt)*) => ($crate::__pr_ratelimited!($crate::printk::Level::Emerg, $($arg)*));
+}
+
+/// Like [`pr_alert!`], but ratelimited. See [`pr_emerg_ratelimited!`].
+#[macro_export]
+macro_rules! pr_alert_ratelimited {
+    ($($arg:tt)*) => ($crate::__pr_ratelimited!($crate::printk::Level::Alert, $($arg)*));
+}
+
+/// Like [`pr_crit!`], but ratelimited. See [`pr_emerg_ratelimited!`].
+#[macro_export]
+macro_rules! pr_crit_ratelimited {
+    ($($arg:tt)*) => ($crate::__pr_ratelimited!($crate::printk::Level::Crit, $($arg)*));
+}
+
+/// Like [`pr_err!`], but ratelimited. See [`pr_emerg_ratelimited!`].
+#[macro_export]
+macro_rules! pr_err_ratelimited {
+    ($($arg:tt)*) => ($crate::__pr_ratelimited!($crate::printk::Level::Err, $($arg)*));
+}
+
+/// Like [`pr_warn!`], but ratelimited. See [`pr_emerg_ratelimited!`].
+#[macro_export]
+macro_rules! pr_warn_ratelimited {
+    ($($arg:tt)*) => ($crate::__pr_ratelimited!($crate::printk::Level::Warning, $($arg)*));
+}
+
+/// Like [`pr_notice!`], but ratelimited. See [`pr_emerg_ratelimited!`].
+#[macro_export]
+macro_rules! pr_notice_ratelimited {
+    ($($arg:tt)*) => ($crate::__pr_ratelimited!($crate::printk::Level::Notice, $($arg)*));
+}
+
+/// Like [`pr_info!`], but ratelimited. See [`pr_emerg_ratelimited!`].
+#[macro_export]
+macro_rules! pr_info_ratelimited {
+    ($($arg:tt)*) => ($crate::__pr_ratelimited!($crate::printk::Level::Info, $($arg)*));
+}
+
+/// Like [`pr_debug!`], but ratelimited. See [`pr_emerg_ratelimited!`].
+#[macro_export]
+macro_rules! pr_debug_ratelimited {
+    ($($arg:tt)*) => ($crate::__pr_ratelimited!($crate::printk::Level::Debug, $($arg)*));
+}
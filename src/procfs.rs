@@ -1,63 +1,292 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ptr;
+
 use crate::bindings;
 use crate::c_types;
-use crate::proc_operations::{ProcOperations, ProcOperationsVtable};
-use crate::types::CStr;
+use crate::error::{Error, KernelResult};
+use crate::file_operations::{
+    Context, FileOperations, OpenAdapter, ProcOperationsVtable, ProcVtable,
+};
+use crate::seq_file::{SeqFileVtable, SeqOperations, SeqShow, SingleFileVtable};
+use crate::types::{CStr, Mode};
+
+extern "C" {
+    fn pde_data_helper(inode: *const bindings::inode) -> *mut c_types::c_void;
+}
+
+struct ProcAdapter;
+
+impl OpenAdapter for ProcAdapter {
+    unsafe fn context(
+        inode: *mut bindings::inode,
+        _file: *mut bindings::file,
+    ) -> *const c_types::c_void {
+        // The entry's data is the context it was created with.
+        pde_data_helper(inode)
+    }
+}
 
+/// An entry in procfs, i.e. a directory, file or symlink. The entries made
+/// by the associated functions are created at the root of procfs; those
+/// made by the `add_*` methods are created in, and owned by, the directory
+/// they are called on. Dropping an entry removes it along with everything
+/// in it.
 pub struct ProcDirEntry {
     raw: *mut bindings::proc_dir_entry,
+    children: Vec<ProcDirEntry>,
+    // The data of a file entry, released once the entry has been removed.
+    _context: Option<Context>,
+}
+
+// Through a shared reference the entry is never used, since adding children
+// takes `&mut self`. The context is only ever dropped, and its type is
+// required to be `Send + Sync` by the registration.
+unsafe impl Sync for ProcDirEntry {}
+
+fn check_entry(raw: *mut bindings::proc_dir_entry) -> KernelResult<*mut bindings::proc_dir_entry> {
+    if raw.is_null() {
+        return Err(Error::ENOMEM);
+    }
+    Ok(raw)
 }
 
 impl ProcDirEntry {
-    pub fn mkdir(
-        name: CStr<'static>,
-        mode: bindings::umode_t,
-        parent: Option<&ProcDirEntry>,
-    ) -> Self {
-        let parent = match parent {
-            Some(parent) => parent.raw,
-            _ => core::ptr::null_mut(),
-        };
-
-        let raw = unsafe {
+    /// Creates a directory. Corresponds to `proc_mkdir_mode`.
+    pub fn mkdir(name: CStr, mode: Mode) -> KernelResult<Self> {
+        Self::mkdir_in(ptr::null_mut(), name, mode)
+    }
+
+    /// Creates a file implemented by `T`, which has no context.
+    pub fn create<T: FileOperations<Context = ()>>(name: CStr, mode: Mode) -> KernelResult<Self> {
+        Self::create_data::<T>(name, mode, Arc::new(()))
+    }
+
+    /// Creates a file implemented by `T`. Every time it is opened,
+    /// [`FileOperations::open`] receives `context`, which is released when
+    /// the entry is removed. Corresponds to `proc_create_data`.
+    pub fn create_data<T: FileOperations>(
+        name: CStr,
+        mode: Mode,
+        context: Arc<T::Context>,
+    ) -> KernelResult<Self> {
+        Self::create_in(
+            ptr::null_mut(),
+            name,
+            mode,
+            &ProcOperationsVtable::<ProcAdapter, T>::VTABLE,
+            Context::new(context),
+        )
+    }
+
+    /// Creates a file whose contents are produced by the sequence `T`, which
+    /// receives `context` in [`SeqOperations::open`].
+    pub fn create_seq<T: SeqOperations>(
+        name: CStr,
+        mode: Mode,
+        context: Arc<T::Context>,
+    ) -> KernelResult<Self> {
+        Self::create_in(
+            ptr::null_mut(),
+            name,
+            mode,
+            &SeqFileVtable::<ProcAdapter, T>::VTABLE,
+            Context::new(context),
+        )
+    }
+
+    /// Creates a file whose contents are printed at once by `T`, which
+    /// receives `context` in [`SeqShow::open`].
+    pub fn create_single<T: SeqShow>(
+        name: CStr,
+        mode: Mode,
+        context: Arc<T::Context>,
+    ) -> KernelResult<Self> {
+        Self::create_in(
+            ptr::null_mut(),
+            name,
+            mode,
+            &SingleFileVtable::<ProcAdapter, T>::VTABLE,
+            Context::new(context),
+        )
+    }
+
+    /// Creates a symlink named `name` that points to `dest`. Corresponds to
+    /// `proc_symlink`.
+    pub fn symlink(name: CStr, dest: CStr) -> KernelResult<Self> {
+        Self::symlink_in(ptr::null_mut(), name, dest)
+    }
+
+    /// Like [`ProcDirEntry::mkdir`], but in this directory.
+    pub fn add_dir(&mut self, name: CStr, mode: Mode) -> KernelResult<&mut Self> {
+        let entry = Self::mkdir_in(self.raw, name, mode)?;
+        Ok(self.add(entry))
+    }
+
+    /// Like [`ProcDirEntry::create`], but in this directory.
+    pub fn add_file<T: FileOperations<Context = ()>>(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+    ) -> KernelResult<&mut Self> {
+        self.add_file_data::<T>(name, mode, Arc::new(()))
+    }
+
+    /// Like [`ProcDirEntry::create_data`], but in this directory.
+    pub fn add_file_data<T: FileOperations>(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+        context: Arc<T::Context>,
+    ) -> KernelResult<&mut Self> {
+        let entry = Self::create_in(
+            self.raw,
+            name,
+            mode,
+            &ProcOperationsVtable::<ProcAdapter, T>::VTABLE,
+            Context::new(context),
+        )?;
+        Ok(self.add(entry))
+    }
+
+    /// Like [`ProcDirEntry::create_seq`], but in this directory.
+    pub fn add_seq<T: SeqOperations>(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+        context: Arc<T::Context>,
+    ) -> KernelResult<&mut Self> {
+        let entry = Self::create_in(
+            self.raw,
+            name,
+            mode,
+            &SeqFileVtable::<ProcAdapter, T>::VTABLE,
+            Context::new(context),
+        )?;
+        Ok(self.add(entry))
+    }
+
+    /// Like [`ProcDirEntry::create_single`], but in this directory.
+    pub fn add_single<T: SeqShow>(
+        &mut self,
+        name: CStr,
+        mode: Mode,
+        context: Arc<T::Context>,
+    ) -> KernelResult<&mut Self> {
+        let entry = Self::create_in(
+            self.raw,
+            name,
+            mode,
+            &SingleFileVtable::<ProcAdapter, T>::VTABLE,
+            Context::new(context),
+        )?;
+        Ok(self.add(entry))
+    }
+
+    /// Like [`ProcDirEntry::symlink`], but in this directory, relative to
+    /// which `dest` is resolved.
+    pub fn add_symlink(&mut self, name: CStr, dest: CStr) -> KernelResult<&mut Self> {
+        let entry = Self::symlink_in(self.raw, name, dest)?;
+        Ok(self.add(entry))
+    }
+
+    fn add(&mut self, entry: ProcDirEntry) -> &mut Self {
+        self.children.push(entry);
+        self.children.last_mut().unwrap()
+    }
+
+    fn mkdir_in(
+        parent: *mut bindings::proc_dir_entry,
+        name: CStr,
+        mode: Mode,
+    ) -> KernelResult<Self> {
+        let raw = check_entry(unsafe {
             bindings::proc_mkdir_mode(
                 name.as_ptr() as *const c_types::c_char,
-                mode,
+                mode.as_int(),
                 parent,
             )
-        };
+        })?;
 
-        Self {
+        Ok(Self {
             raw,
-        }
+            children: Vec::new(),
+            _context: None,
+        })
     }
 
-    pub fn create<T: ProcOperations>(
-        name: CStr<'static>,
-        mode: bindings::umode_t,
-        parent: Option<&ProcDirEntry>,
-    ) -> Self {
-        let parent = match parent {
-            Some(parent) => parent.raw,
-            _ => core::ptr::null_mut(),
-        };
+    fn create_in(
+        parent: *mut bindings::proc_dir_entry,
+        name: CStr,
+        mode: Mode,
+        vtable: &'static ProcVtable,
+        context: Context,
+    ) -> KernelResult<Self> {
+        let raw = check_entry(unsafe {
+            bindings::proc_create_data(
+                name.as_ptr() as *const c_types::c_char,
+                mode.as_int(),
+                parent,
+                vtable,
+                context.ptr as *mut c_types::c_void,
+            )
+        })?;
+
+        Ok(Self {
+            raw,
+            children: Vec::new(),
+            _context: Some(context),
+        })
+    }
 
-        let raw = unsafe {
-            bindings::proc_create(
+    fn symlink_in(
+        parent: *mut bindings::proc_dir_entry,
+        name: CStr,
+        dest: CStr,
+    ) -> KernelResult<Self> {
+        let raw = check_entry(unsafe {
+            bindings::proc_symlink(
                 name.as_ptr() as *const c_types::c_char,
-                mode,
                 parent,
-                &ProcOperationsVtable::<T>::VTABLE,
+                dest.as_ptr() as *const c_types::c_char,
             )
-        };
+        })?;
 
-        Self {
+        Ok(Self {
             raw,
+            children: Vec::new(),
+            _context: None,
+        })
+    }
+
+    /// Sets the size that `stat` reports for the entry, which is 0 by
+    /// default. Corresponds to `proc_set_size`.
+    pub fn set_size(&mut self, size: u64) {
+        unsafe {
+            bindings::proc_set_size(self.raw, size as bindings::loff_t);
+        }
+    }
+
+    /// Sets the owner of the entry, as kernel-internal ids like those of
+    /// [`Credentials`]. Corresponds to `proc_set_user`.
+    ///
+    /// [`Credentials`]: crate::file_operations::Credentials
+    pub fn set_user(&mut self, uid: u32, gid: u32) {
+        unsafe {
+            bindings::proc_set_user(
+                self.raw,
+                bindings::kuid_t { val: uid },
+                bindings::kgid_t { val: gid },
+            );
         }
     }
 }
 
 impl Drop for ProcDirEntry {
     fn drop(&mut self) {
+        // Children remove themselves first, while their entries are still
+        // valid.
+        self.children.clear();
         unsafe {
             bindings::proc_remove(self.raw);
         }
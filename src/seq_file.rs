@@ -0,0 +1,285 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::fmt;
+use core::marker;
+use core::mem;
+use core::ptr;
+
+use crate::bindings;
+use crate::c_types;
+use crate::error::KernelResult;
+use crate::file_operations::{OpenAdapter, ProcVtable};
+
+/// The output buffer of a `seq_file`. Data written to it that does not fit
+/// is not lost: the kernel retries with a larger buffer, or hands it out on
+/// the next `read`.
+///
+/// Implements [`core::fmt::Write`], so it can be used with `write!`.
+pub struct SeqFile {
+    ptr: *mut bindings::seq_file,
+}
+
+impl SeqFile {
+    pub(crate) unsafe fn from_ptr(ptr: *mut bindings::seq_file) -> SeqFile {
+        SeqFile { ptr }
+    }
+
+    /// Appends `data` to the output. Corresponds to `seq_write`.
+    pub fn write(&mut self, data: &[u8]) {
+        // On overflow seq_file marks the buffer as full and takes care of
+        // the retry, so there is nothing to report here.
+        unsafe {
+            bindings::seq_write(
+                self.ptr,
+                data.as_ptr() as *const c_types::c_void,
+                data.len(),
+            );
+        }
+    }
+}
+
+impl fmt::Write for SeqFile {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn start_callback<T: SeqOperations>(
+    m: *mut bindings::seq_file,
+    pos: *mut bindings::loff_t,
+) -> *mut c_types::c_void {
+    let s = &*((*m).private as *const T);
+
+    match s.start(*pos as u64) {
+        Some(item) => Box::into_raw(Box::new(item)) as *mut c_types::c_void,
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn next_callback<T: SeqOperations>(
+    m: *mut bindings::seq_file,
+    v: *mut c_types::c_void,
+    pos: *mut bindings::loff_t,
+) -> *mut c_types::c_void {
+    let s = &*((*m).private as *const T);
+    let item = Box::from_raw(v as *mut T::Item);
+
+    *pos += 1;
+
+    match s.next(*item, *pos as u64) {
+        Some(item) => Box::into_raw(Box::new(item)) as *mut c_types::c_void,
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn stop_callback<T: SeqOperations>(
+    m: *mut bindings::seq_file,
+    v: *mut c_types::c_void,
+) {
+    let s = &*((*m).private as *const T);
+
+    // Reading stops either at the end of the sequence, or in the middle of
+    // it when the buffer is full, in which case we still own the item.
+    if !v.is_null() {
+        drop(Box::from_raw(v as *mut T::Item));
+    }
+
+    s.stop();
+}
+
+unsafe extern "C" fn show_callback<T: SeqOperations>(
+    m: *mut bindings::seq_file,
+    v: *mut c_types::c_void,
+) -> c_types::c_int {
+    let s = &*((*m).private as *const T);
+
+    match s.show(&mut SeqFile::from_ptr(m), &*(v as *const T::Item)) {
+        Ok(()) => 0,
+        Err(e) => e.to_kernel_errno(),
+    }
+}
+
+struct SeqOperationsVtable<T>(marker::PhantomData<T>);
+
+impl<T: SeqOperations> SeqOperationsVtable<T> {
+    const VTABLE: bindings::seq_operations = bindings::seq_operations {
+        start: Some(start_callback::<T>),
+        stop: Some(stop_callback::<T>),
+        next: Some(next_callback::<T>),
+        show: Some(show_callback::<T>),
+    };
+}
+
+unsafe extern "C" fn open_callback<A: OpenAdapter, T: SeqOperations>(
+    inode: *mut bindings::inode,
+    file: *mut bindings::file,
+) -> c_types::c_int {
+    // Borrow the registration's reference rather than taking ownership of it.
+    let context =
+        mem::ManuallyDrop::new(Arc::from_raw(A::context(inode, file) as *const T::Context));
+    let s = match T::open(&context) {
+        Ok(s) => Box::new(s),
+        Err(e) => return e.to_kernel_errno(),
+    };
+
+    let res = bindings::seq_open(file, &SeqOperationsVtable::<T>::VTABLE);
+
+    if res != 0 {
+        return res;
+    }
+
+    let m = (*file).private_data as *mut bindings::seq_file;
+
+    (*m).private = Box::into_raw(s) as *mut c_types::c_void;
+
+    0
+}
+
+unsafe extern "C" fn release_callback<T: SeqOperations>(
+    inode: *mut bindings::inode,
+    file: *mut bindings::file,
+) -> c_types::c_int {
+    let m = (*file).private_data as *mut bindings::seq_file;
+
+    drop(Box::from_raw((*m).private as *mut T));
+
+    bindings::seq_release(inode, file)
+}
+
+unsafe extern "C" fn single_show_callback<T: SeqShow>(
+    m: *mut bindings::seq_file,
+    _v: *mut c_types::c_void,
+) -> c_types::c_int {
+    let s = &*((*m).private as *const T);
+
+    match s.show(&mut SeqFile::from_ptr(m)) {
+        Ok(()) => 0,
+        Err(e) => e.to_kernel_errno(),
+    }
+}
+
+unsafe extern "C" fn single_open_callback<A: OpenAdapter, T: SeqShow>(
+    inode: *mut bindings::inode,
+    file: *mut bindings::file,
+) -> c_types::c_int {
+    let context =
+        mem::ManuallyDrop::new(Arc::from_raw(A::context(inode, file) as *const T::Context));
+    let s = match T::open(&context) {
+        Ok(s) => Box::into_raw(Box::new(s)),
+        Err(e) => return e.to_kernel_errno(),
+    };
+
+    let res = bindings::single_open(
+        file,
+        Some(single_show_callback::<T>),
+        s as *mut c_types::c_void,
+    );
+
+    if res != 0 {
+        drop(Box::from_raw(s));
+    }
+
+    res
+}
+
+unsafe extern "C" fn single_release_callback<T: SeqShow>(
+    inode: *mut bindings::inode,
+    file: *mut bindings::file,
+) -> c_types::c_int {
+    let m = (*file).private_data as *mut bindings::seq_file;
+
+    drop(Box::from_raw((*m).private as *mut T));
+
+    bindings::single_release(inode, file)
+}
+
+pub(crate) struct SeqFileVtable<A, T>(marker::PhantomData<A>, marker::PhantomData<T>);
+
+// Reading and seeking are entirely handled by seq_file.
+impl<A: OpenAdapter, T: SeqOperations> SeqFileVtable<A, T> {
+    pub(crate) const VTABLE: ProcVtable = proc_vtable! {
+        open: Some(open_callback::<A, T>),
+        release: Some(release_callback::<T>),
+        read: Some(bindings::seq_read),
+        read_iter: None,
+        write: None,
+        llseek: Some(bindings::seq_lseek),
+        ioctl: None,
+        compat_ioctl: None,
+        poll: None,
+        mmap: None,
+    };
+}
+
+pub(crate) struct SingleFileVtable<A, T>(marker::PhantomData<A>, marker::PhantomData<T>);
+
+impl<A: OpenAdapter, T: SeqShow> SingleFileVtable<A, T> {
+    pub(crate) const VTABLE: ProcVtable = proc_vtable! {
+        open: Some(single_open_callback::<A, T>),
+        release: Some(single_release_callback::<T>),
+        read: Some(bindings::seq_read),
+        read_iter: None,
+        write: None,
+        llseek: Some(bindings::seq_lseek),
+        ioctl: None,
+        compat_ioctl: None,
+        poll: None,
+        mmap: None,
+    };
+}
+
+/// `SeqOperations` corresponds to the kernel's `struct seq_operations`. It
+/// produces the contents of a file as a sequence of items, each of which is
+/// printed by [`SeqOperations::show`]. The kernel takes care of splitting
+/// the output across `read` calls and of seeking, by restarting the
+/// sequence at the right position.
+pub trait SeqOperations: Sync + Sized {
+    /// The state shared by every instance of this file, which is passed to
+    /// the registration (e.g. [`ProcDirEntry::create_seq`]) and handed to
+    /// [`SeqOperations::open`]. Use `()` if there is none.
+    ///
+    /// [`ProcDirEntry::create_seq`]: crate::procfs::ProcDirEntry::create_seq
+    type Context: Send + Sync;
+
+    /// The cursor into the sequence.
+    type Item;
+
+    /// Creates a new instance of this file.
+    fn open(context: &Arc<Self::Context>) -> KernelResult<Self>;
+
+    /// Returns the item at position `pos`, or `None` if the sequence has
+    /// fewer items. Corresponds to the `start` function pointer in
+    /// `struct seq_operations`.
+    fn start(&self, pos: u64) -> Option<Self::Item>;
+
+    /// Returns the item following `item`, which is at position `pos`, or
+    /// `None` at the end of the sequence. Corresponds to the `next` function
+    /// pointer in `struct seq_operations`.
+    fn next(&self, item: Self::Item, pos: u64) -> Option<Self::Item>;
+
+    /// Called after a batch of items has been shown. Corresponds to the
+    /// `stop` function pointer in `struct seq_operations`.
+    fn stop(&self) {}
+
+    /// Prints `item` to `m`. Corresponds to the `show` function pointer in
+    /// `struct seq_operations`.
+    fn show(&self, m: &mut SeqFile, item: &Self::Item) -> KernelResult<()>;
+}
+
+/// A file whose whole contents are printed by a single call to
+/// [`SeqShow::show`]. Corresponds to `single_open`.
+pub trait SeqShow: Sync + Sized {
+    /// The state shared by every instance of this file, which is passed to
+    /// the registration (e.g. [`ProcDirEntry::create_single`]) and handed to
+    /// [`SeqShow::open`]. Use `()` if there is none.
+    ///
+    /// [`ProcDirEntry::create_single`]: crate::procfs::ProcDirEntry::create_single
+    type Context: Send + Sync;
+
+    /// Creates a new instance of this file.
+    fn open(context: &Arc<Self::Context>) -> KernelResult<Self>;
+
+    /// Prints the contents of the file to `m`.
+    fn show(&self, m: &mut SeqFile) -> KernelResult<()>;
+}
@@ -1,10 +1,55 @@
-use core::ops::{Deref, DerefMut};
 use crate::bindings;
+use core::cell::UnsafeCell;
+use core::mem;
+use core::ops::{Deref, DerefMut};
 
 extern "C" {
+    fn spin_lock_init_helper(lock: *mut bindings::spinlock_t);
+    fn spin_lock_helper(lock: *const bindings::spinlock_t);
     fn spin_unlock_helper(lock: *const bindings::spinlock_t);
 }
 
+/// A value of type `T` protected by a kernel `spinlock_t`.
+///
+/// The guard must not be held across anything that might sleep, such as
+/// allocating with `GFP_KERNEL` or copying to and from userspace.
+pub struct Spinlock<T> {
+    lock: UnsafeCell<bindings::spinlock_t>,
+    data: UnsafeCell<T>,
+}
+
+// This is safe because the data is only accessed while holding the lock.
+unsafe impl<T: Send> Send for Spinlock<T> {}
+unsafe impl<T: Send> Sync for Spinlock<T> {}
+
+impl<T> Spinlock<T> {
+    pub fn new(data: T) -> Self {
+        let lock = Self {
+            lock: UnsafeCell::new(unsafe { mem::zeroed() }),
+            data: UnsafeCell::new(data),
+        };
+
+        unsafe {
+            spin_lock_init_helper(lock.lock.get());
+        }
+
+        lock
+    }
+
+    /// Spins until the lock is available, and returns a guard that gives
+    /// access to the data and releases the lock when dropped.
+    pub fn lock(&self) -> SpinlockGuard<&mut T> {
+        unsafe {
+            spin_lock_helper(self.lock.get());
+        }
+
+        SpinlockGuard {
+            inner: unsafe { &mut *self.data.get() },
+            lock: self.lock.get(),
+        }
+    }
+}
+
 pub struct SpinlockGuard<T> {
     pub(crate) inner: T,
     pub(crate) lock: *mut bindings::spinlock,
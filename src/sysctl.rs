@@ -1,18 +1,98 @@
 use alloc::boxed::Box;
+use alloc::sync::Arc;
 use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp;
+use core::convert::TryFrom;
+use core::fmt::{self, Write};
 use core::mem;
+use core::ops::Deref;
 use core::ptr;
+use core::str;
 use core::sync::atomic;
 
 use crate::bindings;
 use crate::c_types;
 use crate::error;
+use crate::spinlock::Spinlock;
 use crate::types;
-use crate::user_ptr::{UserSlicePtr, UserSlicePtrWriter};
+#[cfg(not(kernel_5_8_0_or_greater))]
+use crate::user_ptr::UserSlicePtr;
+
+/// The rendered value of a sysctl, from which reads at any offset are
+/// served.
+///
+/// Implements [`core::fmt::Write`], so it can be used with `write!`.
+pub struct SysctlBuffer {
+    data: Vec<u8>,
+    requested: usize,
+}
+
+impl SysctlBuffer {
+    /// Returns how much of the value the `read` being served needs, i.e.
+    /// its offset plus its length. Only values that can be rendered to any
+    /// length, like a stream of random bytes, need to look at this; the
+    /// rest of the value is discarded.
+    pub fn requested(&self) -> usize {
+        self.requested
+    }
+
+    /// Makes room for at least `additional` more bytes, so that writing
+    /// them doesn't allocate, e.g. while holding a spinlock.
+    ///
+    /// Returns ENOMEM if the allocation fails.
+    pub fn reserve(&mut self, additional: usize) -> error::KernelResult<()> {
+        self.data
+            .try_reserve(additional)
+            .map_err(|_| error::Error::ENOMEM)
+    }
+
+    /// Appends `data` to the value.
+    ///
+    /// Returns ENOMEM if the buffer cannot grow.
+    pub fn write(&mut self, data: &[u8]) -> error::KernelResult<()> {
+        self.reserve(data.len())?;
+        self.data.extend_from_slice(data);
+        Ok(())
+    }
+}
+
+impl Write for SysctlBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
 
 pub trait SysctlStorage: Sync {
+    /// Whether a write at a non-zero offset continues the value written so
+    /// far, like `proc_dostring`. Otherwise such writes are ignored, like
+    /// `proc_dointvec` does.
+    fn append_writes(&self) -> bool {
+        false
+    }
+
     fn store_value(&self, data: &[u8]) -> (usize, error::KernelResult<()>);
-    fn read_value(&self, data: &mut UserSlicePtrWriter) -> (usize, error::KernelResult<()>);
+
+    /// Renders the current value into `buf`. Called for every `read`, which
+    /// is then served from the offset it was made at.
+    fn read_value(&self, buf: &mut SysctlBuffer) -> error::KernelResult<()>;
+}
+
+/// Storage whose writes are parsed into a value before that value is
+/// published, which lets [`OnChange`] look at the new value in between.
+pub trait SysctlParse: SysctlStorage {
+    type Value;
+
+    /// Parses a write. Nothing is published if this returns an error.
+    fn parse(&self, data: &[u8]) -> error::KernelResult<Self::Value>;
+
+    /// Makes `value` the current value.
+    fn publish(&self, value: Self::Value);
+}
+
+fn store_parsed<T: SysctlParse>(storage: &T, data: &[u8]) -> (usize, error::KernelResult<()>) {
+    let result = storage.parse(data).map(|value| storage.publish(value));
+    (data.len(), result)
 }
 
 fn trim_whitespace(mut data: &[u8]) -> &[u8] {
@@ -33,45 +113,389 @@ impl<T> SysctlStorage for &T
 where
     T: SysctlStorage,
 {
+    fn append_writes(&self) -> bool {
+        (*self).append_writes()
+    }
+
     fn store_value(&self, data: &[u8]) -> (usize, error::KernelResult<()>) {
         (*self).store_value(data)
     }
 
-    fn read_value(&self, data: &mut UserSlicePtrWriter) -> (usize, error::KernelResult<()>) {
-        (*self).read_value(data)
+    fn read_value(&self, buf: &mut SysctlBuffer) -> error::KernelResult<()> {
+        (*self).read_value(buf)
+    }
+}
+
+impl SysctlParse for atomic::AtomicBool {
+    type Value = bool;
+
+    fn parse(&self, data: &[u8]) -> error::KernelResult<bool> {
+        match trim_whitespace(data) {
+            b"0" => Ok(false),
+            b"1" => Ok(true),
+            _ => Err(error::Error::EINVAL),
+        }
+    }
+
+    fn publish(&self, value: bool) {
+        self.store(value, atomic::Ordering::Relaxed);
     }
 }
 
 impl SysctlStorage for atomic::AtomicBool {
     fn store_value(&self, data: &[u8]) -> (usize, error::KernelResult<()>) {
-        let result = match trim_whitespace(data) {
-            b"0" => {
-                self.store(false, atomic::Ordering::Relaxed);
-                Ok(())
-            }
-            b"1" => {
-                self.store(true, atomic::Ordering::Relaxed);
-                Ok(())
-            }
-            _ => Err(error::Error::EINVAL),
-        };
-        (data.len(), result)
+        store_parsed(self, data)
     }
 
-    fn read_value(&self, data: &mut UserSlicePtrWriter) -> (usize, error::KernelResult<()>) {
+    fn read_value(&self, buf: &mut SysctlBuffer) -> error::KernelResult<()> {
         let value = if self.load(atomic::Ordering::Relaxed) {
             b"1\n"
         } else {
             b"0\n"
         };
-        (value.len(), data.write(value))
+        buf.write(value)
+    }
+}
+
+/// An atomic integer type that can be exposed as a sysctl. Values are read
+/// in decimal, and written in decimal or, with a `0x` prefix, in hex.
+pub trait SysctlInteger: Sync {
+    type Value: Copy + Sync + PartialOrd + fmt::Display + TryFrom<i128>;
+
+    fn get(&self) -> Self::Value;
+    fn set(&self, value: Self::Value);
+}
+
+fn parse_integer<T: TryFrom<i128>>(data: &[u8]) -> error::KernelResult<T> {
+    let s = str::from_utf8(trim_whitespace(data))?;
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let (radix, digits) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(digits) => (16, digits),
+        None => (10, s),
+    };
+    // from_str_radix accepts a sign of its own.
+    if digits.starts_with('+') || digits.starts_with('-') {
+        return Err(error::Error::EINVAL);
+    }
+    let magnitude = u128::from_str_radix(digits, radix).map_err(|_| error::Error::EINVAL)?;
+    let value = i128::try_from(magnitude).map_err(|_| error::Error::EINVAL)?;
+    let value = if negative { -value } else { value };
+    T::try_from(value).map_err(|_| error::Error::EINVAL)
+}
+
+fn read_integer<T: SysctlInteger>(storage: &T, buf: &mut SysctlBuffer) -> error::KernelResult<()> {
+    writeln!(buf, "{}", storage.get()).map_err(|_| error::Error::ENOMEM)
+}
+
+macro_rules! sysctl_integer {
+    ($($atomic:ty => $int:ty,)*) => {
+        $(
+            impl SysctlInteger for $atomic {
+                type Value = $int;
+
+                fn get(&self) -> $int {
+                    self.load(atomic::Ordering::Relaxed)
+                }
+
+                fn set(&self, value: $int) {
+                    self.store(value, atomic::Ordering::Relaxed)
+                }
+            }
+
+            impl SysctlParse for $atomic {
+                type Value = $int;
+
+                fn parse(&self, data: &[u8]) -> error::KernelResult<$int> {
+                    parse_integer(data)
+                }
+
+                fn publish(&self, value: $int) {
+                    self.set(value);
+                }
+            }
+
+            impl SysctlStorage for $atomic {
+                fn store_value(&self, data: &[u8]) -> (usize, error::KernelResult<()>) {
+                    store_parsed(self, data)
+                }
+
+                fn read_value(&self, buf: &mut SysctlBuffer) -> error::KernelResult<()> {
+                    read_integer(self, buf)
+                }
+            }
+        )*
+    };
+}
+
+sysctl_integer! {
+    atomic::AtomicI32 => i32,
+    atomic::AtomicU32 => u32,
+    atomic::AtomicI64 => i64,
+    atomic::AtomicU64 => u64,
+    atomic::AtomicUsize => usize,
+}
+
+/// An integer sysctl that rejects writes outside of `min..=max` with
+/// EINVAL, like `proc_dointvec_minmax`. Dereferences to the wrapped
+/// integer.
+pub struct Bounded<T: SysctlInteger> {
+    value: T,
+    min: T::Value,
+    max: T::Value,
+}
+
+impl<T: SysctlInteger> Bounded<T> {
+    pub fn new(value: T, min: T::Value, max: T::Value) -> Self {
+        Self { value, min, max }
+    }
+}
+
+impl<T: SysctlInteger> Deref for Bounded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: SysctlInteger> SysctlParse for Bounded<T> {
+    type Value = T::Value;
+
+    fn parse(&self, data: &[u8]) -> error::KernelResult<T::Value> {
+        let value = parse_integer(data)?;
+        if value < self.min || value > self.max {
+            return Err(error::Error::EINVAL);
+        }
+        Ok(value)
+    }
+
+    fn publish(&self, value: T::Value) {
+        self.value.set(value);
+    }
+}
+
+impl<T: SysctlInteger> SysctlStorage for Bounded<T> {
+    fn store_value(&self, data: &[u8]) -> (usize, error::KernelResult<()>) {
+        store_parsed(self, data)
+    }
+
+    fn read_value(&self, buf: &mut SysctlBuffer) -> error::KernelResult<()> {
+        read_integer(&self.value, buf)
+    }
+}
+
+// Like `proc_dostring`, a write only considers the input up to the first
+// newline or NUL byte.
+fn first_line(data: &[u8]) -> &[u8] {
+    match data.iter().position(|&c| c == b'\n' || c == 0) {
+        Some(end) => &data[..end],
+        None => data,
+    }
+}
+
+/// A string sysctl of at most `capacity` bytes, protected by a spinlock.
+/// Follows `proc_dostring`: a write stores its input up to the first newline,
+/// silently truncated to `capacity` bytes, and a read returns the value
+/// followed by a newline. The value is not required to be UTF-8.
+pub struct SysctlString {
+    value: Spinlock<Vec<u8>>,
+    capacity: usize,
+}
+
+impl SysctlString {
+    /// Creates a string sysctl holding `initial`, truncated to `capacity`
+    /// bytes.
+    ///
+    /// Returns ENOMEM if the buffer cannot be allocated.
+    pub fn new(initial: &[u8], capacity: usize) -> error::KernelResult<SysctlString> {
+        // Allocate the whole buffer up front, so that writes never have to
+        // allocate while holding the lock.
+        let mut value = Vec::new();
+        value
+            .try_reserve_exact(capacity)
+            .map_err(|_| error::Error::ENOMEM)?;
+        value.extend_from_slice(&initial[..cmp::min(initial.len(), capacity)]);
+        Ok(SysctlString {
+            value: Spinlock::new(value),
+            capacity,
+        })
+    }
+
+    /// Calls `f` with the current value, while holding the lock.
+    pub fn with_value<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.value.lock())
+    }
+
+    /// Replaces the value with `value`, truncated to the capacity.
+    pub fn set(&self, value: &[u8]) {
+        let value = &value[..cmp::min(value.len(), self.capacity)];
+        let mut guard = self.value.lock();
+        guard.clear();
+        guard.extend_from_slice(value);
+    }
+}
+
+impl SysctlParse for SysctlString {
+    type Value = Vec<u8>;
+
+    /// Copies the new value out of the write, already truncated.
+    ///
+    /// Returns ENOMEM if the copy cannot be allocated.
+    fn parse(&self, data: &[u8]) -> error::KernelResult<Vec<u8>> {
+        let line = first_line(data);
+        let line = &line[..cmp::min(line.len(), self.capacity)];
+        let mut value = Vec::new();
+        value
+            .try_reserve_exact(line.len())
+            .map_err(|_| error::Error::ENOMEM)?;
+        value.extend_from_slice(line);
+        Ok(value)
+    }
+
+    fn publish(&self, value: Vec<u8>) {
+        self.set(&value);
+    }
+}
+
+impl SysctlStorage for SysctlString {
+    fn append_writes(&self) -> bool {
+        true
+    }
+
+    fn store_value(&self, data: &[u8]) -> (usize, error::KernelResult<()>) {
+        store_parsed(self, data)
+    }
+
+    fn read_value(&self, buf: &mut SysctlBuffer) -> error::KernelResult<()> {
+        // Allocating might sleep, so it can't be done under the spinlock.
+        buf.reserve(self.capacity + 1)?;
+        self.with_value(|v| buf.write(v))?;
+        buf.write(b"\n")
+    }
+}
+
+/// A sysctl that holds one of a fixed set of keywords, e.g. the name of a
+/// mode. A write must be exactly one of the keywords, up to the first
+/// newline, and a read returns the current keyword followed by a newline.
+pub struct SysctlChoice {
+    choices: &'static [&'static str],
+    current: atomic::AtomicUsize,
+}
+
+impl SysctlChoice {
+    /// Creates a sysctl choosing between `choices`, initially set to
+    /// `choices[initial]`.
+    ///
+    /// Panics if `initial` is out of bounds.
+    pub fn new(choices: &'static [&'static str], initial: usize) -> SysctlChoice {
+        assert!(initial < choices.len());
+        SysctlChoice {
+            choices,
+            current: atomic::AtomicUsize::new(initial),
+        }
+    }
+
+    /// Returns the index of the current keyword in the choices.
+    pub fn get(&self) -> usize {
+        self.current.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Returns the current keyword.
+    pub fn get_str(&self) -> &'static str {
+        self.choices[self.get()]
+    }
+}
+
+impl SysctlParse for SysctlChoice {
+    /// The index of the chosen keyword.
+    type Value = usize;
+
+    fn parse(&self, data: &[u8]) -> error::KernelResult<usize> {
+        let input = first_line(data);
+        self.choices
+            .iter()
+            .position(|c| c.as_bytes() == input)
+            .ok_or(error::Error::EINVAL)
+    }
+
+    fn publish(&self, value: usize) {
+        self.current.store(value, atomic::Ordering::Relaxed);
+    }
+}
+
+impl SysctlStorage for SysctlChoice {
+    fn store_value(&self, data: &[u8]) -> (usize, error::KernelResult<()>) {
+        store_parsed(self, data)
+    }
+
+    fn read_value(&self, buf: &mut SysctlBuffer) -> error::KernelResult<()> {
+        buf.write(self.get_str().as_bytes())?;
+        buf.write(b"\n")
+    }
+}
+
+/// Wraps a storage with a hook that runs on every write, after the input has
+/// been parsed and before the new value is published. The hook can reject
+/// the write by returning an error, which is passed on to the writer, or act
+/// on the new value, e.g. by resizing a buffer. Dereferences to the wrapped
+/// storage.
+///
+/// The hook runs without any lock held, so concurrent writes can be
+/// published in a different order than their hooks ran.
+pub struct OnChange<T: SysctlParse, F> {
+    storage: T,
+    hook: F,
+}
+
+impl<T, F> OnChange<T, F>
+where
+    T: SysctlParse,
+    F: Fn(&T::Value) -> error::KernelResult<()> + Sync,
+{
+    pub fn new(storage: T, hook: F) -> Self {
+        OnChange { storage, hook }
+    }
+}
+
+impl<T: SysctlParse, F> Deref for OnChange<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.storage
+    }
+}
+
+impl<T, F> SysctlStorage for OnChange<T, F>
+where
+    T: SysctlParse,
+    F: Fn(&T::Value) -> error::KernelResult<()> + Sync,
+{
+    fn append_writes(&self) -> bool {
+        self.storage.append_writes()
+    }
+
+    fn store_value(&self, data: &[u8]) -> (usize, error::KernelResult<()>) {
+        let result = self.storage.parse(data).and_then(|value| {
+            (self.hook)(&value)?;
+            self.storage.publish(value);
+            Ok(())
+        });
+        (data.len(), result)
+    }
+
+    fn read_value(&self, buf: &mut SysctlBuffer) -> error::KernelResult<()> {
+        self.storage.read_value(buf)
     }
 }
 
 pub struct Sysctl<T: SysctlStorage> {
     inner: Box<T>,
-    // Responsible for keeping the ctl_table alive.
+    // Responsible for keeping the ctl_table and its name alive.
     _table: Box<[bindings::ctl_table]>,
+    _name: types::CString,
     header: *mut bindings::ctl_table_header,
 }
 
@@ -79,6 +503,99 @@ pub struct Sysctl<T: SysctlStorage> {
 // &T, and T: Sync. Any new methods must adhere to this requirement.
 unsafe impl<T: SysctlStorage> Sync for Sysctl<T> {}
 
+// Since 5.8, the kernel copies the buffer of a sysctl from and to userspace
+// itself, and hands the handler a kernel buffer.
+#[cfg(kernel_5_8_0_or_greater)]
+unsafe fn copy_in(buffer: *mut c_types::c_void, len: usize) -> error::KernelResult<Vec<u8>> {
+    let mut data = Vec::new();
+    data.try_reserve_exact(len)
+        .map_err(|_| error::Error::ENOMEM)?;
+    data.extend_from_slice(core::slice::from_raw_parts(buffer as *const u8, len));
+    Ok(data)
+}
+
+#[cfg(kernel_5_8_0_or_greater)]
+unsafe fn copy_out(buffer: *mut c_types::c_void, data: &[u8]) -> error::KernelResult<()> {
+    ptr::copy_nonoverlapping(data.as_ptr(), buffer as *mut u8, data.len());
+    Ok(())
+}
+
+#[cfg(not(kernel_5_8_0_or_greater))]
+unsafe fn copy_in(buffer: *mut c_types::c_void, len: usize) -> error::KernelResult<Vec<u8>> {
+    UserSlicePtr::new(buffer, len)?.read_all()
+}
+
+#[cfg(not(kernel_5_8_0_or_greater))]
+unsafe fn copy_out(buffer: *mut c_types::c_void, data: &[u8]) -> error::KernelResult<()> {
+    UserSlicePtr::new(buffer, data.len())?.write_all(data)
+}
+
+fn render<T: SysctlStorage>(storage: &T, requested: usize) -> error::KernelResult<Vec<u8>> {
+    let mut buf = SysctlBuffer {
+        data: Vec::new(),
+        requested,
+    };
+    storage.read_value(&mut buf)?;
+    Ok(buf.data)
+}
+
+unsafe fn read_at<T: SysctlStorage>(
+    storage: &T,
+    buffer: *mut c_types::c_void,
+    len: &mut usize,
+    ppos: &mut bindings::loff_t,
+) -> error::KernelResult<()> {
+    let requested = (*ppos as usize).saturating_add(*len);
+    let value = render(storage, requested)?;
+    let start = cmp::min(*ppos as u64, value.len() as u64) as usize;
+    let data = &value[start..];
+    let data = &data[..cmp::min(*len, data.len())];
+    copy_out(buffer, data)?;
+    *len = data.len();
+    *ppos += data.len() as bindings::loff_t;
+    Ok(())
+}
+
+unsafe fn write_at<T: SysctlStorage>(
+    storage: &T,
+    buffer: *mut c_types::c_void,
+    len: &mut usize,
+    ppos: &mut bindings::loff_t,
+) -> error::KernelResult<()> {
+    let data = copy_in(buffer, *len)?;
+    if *ppos == 0 {
+        let (bytes_processed, result) = storage.store_value(&data);
+        *len = bytes_processed;
+        *ppos += *len as bindings::loff_t;
+        return result;
+    }
+
+    if !storage.append_writes() {
+        // Like proc_dointvec, nothing is written.
+        *len = 0;
+        return Ok(());
+    }
+
+    // Like proc_dostring, continue the current value at the offset, unless
+    // that is past its end, in which case nothing is written.
+    let mut value = render(storage, *ppos as usize)?;
+    if value.last() == Some(&b'\n') {
+        value.pop();
+    }
+    if *ppos as u64 > value.len() as u64 {
+        *len = 0;
+        return Ok(());
+    }
+    value.truncate(*ppos as usize);
+    value
+        .try_reserve(data.len())
+        .map_err(|_| error::Error::ENOMEM)?;
+    value.extend_from_slice(&data);
+    storage.store_value(&value).1?;
+    *ppos += *len as bindings::loff_t;
+    Ok(())
+}
+
 unsafe extern "C" fn proc_handler<T: SysctlStorage>(
     ctl: *mut bindings::ctl_table,
     write: c_types::c_int,
@@ -86,40 +603,62 @@ unsafe extern "C" fn proc_handler<T: SysctlStorage>(
     len: *mut usize,
     ppos: *mut bindings::loff_t,
 ) -> c_types::c_int {
-    // If we're reading from some offset other than the beginning of the file,
-    // return an empty read to signal EOF.
-    if *ppos != 0 && write == 0 {
-        *len = 0;
-        return 0;
-    }
-
-    let data = match UserSlicePtr::new(buffer, *len) {
-        Ok(ptr) => ptr,
-        Err(e) => return e.to_kernel_errno(),
-    };
     let storage = &*((*ctl).data as *const T);
-    let (bytes_processed, result) = if write != 0 {
-        let data = match data.read_all() {
-            Ok(r) => r,
-            Err(e) => return e.to_kernel_errno(),
-        };
-        storage.store_value(&data)
+    let result = if write != 0 {
+        write_at(storage, buffer, &mut *len, &mut *ppos)
     } else {
-        let mut writer = data.writer();
-        storage.read_value(&mut writer)
+        read_at(storage, buffer, &mut *len, &mut *ppos)
     };
-    *len = bytes_processed;
-    *ppos += *len as bindings::loff_t;
     match result {
         Ok(()) => 0,
         Err(e) => e.to_kernel_errno(),
     }
 }
 
+// Builds the `ctl_table` entry for a directory whose contents are `child`,
+// which must outlive the registration of the table, as must `name`.
+fn directory_entry(name: &types::CString, child: *mut bindings::ctl_table) -> bindings::ctl_table {
+    bindings::ctl_table {
+        procname: name.as_ptr() as *const i8,
+        mode: 0o555,
+        data: ptr::null_mut(),
+        proc_handler: None,
+
+        maxlen: 0,
+        child,
+        poll: ptr::null_mut(),
+        extra1: ptr::null_mut(),
+        extra2: ptr::null_mut(),
+    }
+}
+
+// Builds the `ctl_table` entry for a file whose `data` is handled by
+// `proc_handler`. Both `name` and `data` must outlive the registration of
+// the table.
+fn file_entry(
+    name: &types::CString,
+    data: *const c_types::c_void,
+    proc_handler: bindings::proc_handler,
+    mode: types::Mode,
+) -> bindings::ctl_table {
+    bindings::ctl_table {
+        procname: name.as_ptr() as *const i8,
+        mode: mode.as_int(),
+        data: data as *mut c_types::c_void,
+        proc_handler,
+
+        maxlen: 0,
+        child: ptr::null_mut(),
+        poll: ptr::null_mut(),
+        extra1: ptr::null_mut(),
+        extra2: ptr::null_mut(),
+    }
+}
+
 impl<T: SysctlStorage> Sysctl<T> {
     pub fn register(
-        path: types::CStr<'static>,
-        name: types::CStr<'static>,
+        path: types::CStr,
+        name: types::CStr,
         storage: T,
         mode: types::Mode,
     ) -> error::KernelResult<Sysctl<T>> {
@@ -127,20 +666,15 @@ impl<T: SysctlStorage> Sysctl<T> {
             return Err(error::Error::EINVAL);
         }
 
+        let name = types::CString::from_cstr(name)?;
         let storage = Box::new(storage);
         let mut table = vec![
-            bindings::ctl_table {
-                procname: name.as_ptr() as *const i8,
-                mode: mode.as_int(),
-                data: &*storage as *const T as *mut c_types::c_void,
-                proc_handler: Some(proc_handler::<T>),
-
-                maxlen: 0,
-                child: ptr::null_mut(),
-                poll: ptr::null_mut(),
-                extra1: ptr::null_mut(),
-                extra2: ptr::null_mut(),
-            },
+            file_entry(
+                &name,
+                &*storage as *const T as *const c_types::c_void,
+                Some(proc_handler::<T>),
+                mode,
+            ),
             unsafe { mem::zeroed() },
         ]
         .into_boxed_slice();
@@ -154,6 +688,7 @@ impl<T: SysctlStorage> Sysctl<T> {
         Ok(Sysctl {
             inner: storage,
             _table: table,
+            _name: name,
             header: result,
         })
     }
@@ -171,3 +706,158 @@ impl<T: SysctlStorage> Drop for Sysctl<T> {
         self.header = ptr::null_mut();
     }
 }
+
+struct File<'a> {
+    name: types::CStr<'a>,
+    mode: types::Mode,
+    // Points into `storage`, with its concrete type known to `proc_handler`.
+    data: *const c_types::c_void,
+    proc_handler: bindings::proc_handler,
+    storage: Arc<dyn SysctlStorage + Send>,
+}
+
+/// A directory of sysctls, which can contain files and further
+/// subdirectories. Registering it with [`Directory::register`] creates the
+/// whole tree at once.
+pub struct Directory<'a> {
+    files: Vec<File<'a>>,
+    subdirectories: Vec<(types::CStr<'a>, Directory<'a>)>,
+}
+
+#[allow(clippy::new_without_default)]
+impl<'a> Directory<'a> {
+    /// Creates a new, empty directory.
+    pub fn new() -> Directory<'a> {
+        Directory {
+            files: vec![],
+            subdirectories: vec![],
+        }
+    }
+
+    /// Adds a file named `name` backed by `storage`. The module can keep a
+    /// clone of `storage` to access the value.
+    pub fn entry<T: SysctlStorage + Send + 'static>(
+        mut self,
+        name: types::CStr<'a>,
+        storage: Arc<T>,
+        mode: types::Mode,
+    ) -> Directory<'a> {
+        self.files.push(File {
+            name,
+            mode,
+            data: &*storage as *const T as *const c_types::c_void,
+            proc_handler: Some(proc_handler::<T>),
+            storage,
+        });
+        self
+    }
+
+    /// Adds a subdirectory named `name` with the contents of `directory`.
+    pub fn subdirectory(
+        mut self,
+        name: types::CStr<'a>,
+        directory: Directory<'a>,
+    ) -> Directory<'a> {
+        self.subdirectories.push((name, directory));
+        self
+    }
+
+    /// Registers the tree under `path`, e.g. `cstr!("rust/example")`. The
+    /// whole tree is registered as a single table, so it appears at once,
+    /// and is unregistered at once when the returned [`Registration`] is
+    /// dropped.
+    ///
+    /// Returns EINVAL if a file or subdirectory name contains a `/`.
+    pub fn register(self, path: types::CStr) -> error::KernelResult<Registration> {
+        let mut registration = Registration {
+            header: ptr::null_mut(),
+            _tables: vec![],
+            _names: vec![],
+            _storage: vec![],
+        };
+        let mut table = registration.build_table(self)?;
+        // Nest the tree in a directory entry for each component of `path`,
+        // innermost first.
+        for component in path
+            .trim_end_matches('\x00')
+            .rsplit('/')
+            .filter(|c| !c.is_empty())
+        {
+            let name = crate::kformat!("{}", component)?;
+            table = registration.push_table(vec![directory_entry(&name, table)]);
+            registration._names.push(name);
+        }
+
+        let header = unsafe { bindings::register_sysctl_table(table) };
+        if header.is_null() {
+            return Err(error::Error::ENOMEM);
+        }
+        registration.header = header;
+        Ok(registration)
+    }
+}
+
+/// A tree of sysctls registered by [`Directory::register`].
+pub struct Registration {
+    header: *mut bindings::ctl_table_header,
+    // Responsible for keeping the tables, their names and their storage
+    // alive while registered.
+    _tables: Vec<Box<[bindings::ctl_table]>>,
+    _names: Vec<types::CString>,
+    _storage: Vec<Arc<dyn SysctlStorage + Send>>,
+}
+
+// This is safe because Registration doesn't actually expose any methods, and
+// the storage is only accessed through shared references from the sysctl
+// handlers.
+unsafe impl Sync for Registration {}
+
+impl Registration {
+    // Builds the table of `directory` and, recursively, those of its
+    // subdirectories, which are linked as children of their entries.
+    fn build_table(
+        &mut self,
+        directory: Directory,
+    ) -> error::KernelResult<*mut bindings::ctl_table> {
+        let mut entries = Vec::new();
+        for file in directory.files {
+            if file.name.contains('/') {
+                return Err(error::Error::EINVAL);
+            }
+            let name = types::CString::from_cstr(file.name)?;
+            entries.push(file_entry(&name, file.data, file.proc_handler, file.mode));
+            self._names.push(name);
+            self._storage.push(file.storage);
+        }
+        for (name, subdirectory) in directory.subdirectories {
+            if name.contains('/') {
+                return Err(error::Error::EINVAL);
+            }
+            let name = types::CString::from_cstr(name)?;
+            let child = self.build_table(subdirectory)?;
+            entries.push(directory_entry(&name, child));
+            self._names.push(name);
+        }
+        Ok(self.push_table(entries))
+    }
+
+    // Terminates `entries` and keeps the resulting table alive for as long
+    // as the registration.
+    fn push_table(&mut self, mut entries: Vec<bindings::ctl_table>) -> *mut bindings::ctl_table {
+        entries.push(unsafe { mem::zeroed() });
+        let mut table = entries.into_boxed_slice();
+        let ptr = table.as_mut_ptr();
+        self._tables.push(table);
+        ptr
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if !self.header.is_null() {
+            unsafe {
+                bindings::unregister_sysctl_table(self.header);
+            }
+        }
+    }
+}
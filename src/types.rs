@@ -1,16 +1,55 @@
+use alloc::string::String;
+use core::fmt;
 use core::ops::Deref;
+use core::str;
 
 use crate::bindings;
+use crate::error::{Error, KernelResult};
 
-pub struct Mode(bindings::umode_t);
+bitflags::bitflags! {
+    /// The type and permission bits of a file. Corresponds to the kernel's
+    /// `umode_t`.
+    pub struct Mode: bindings::umode_t {
+        const S_IFMT = bindings::S_IFMT as bindings::umode_t;
+        const S_IFSOCK = bindings::S_IFSOCK as bindings::umode_t;
+        const S_IFLNK = bindings::S_IFLNK as bindings::umode_t;
+        const S_IFREG = bindings::S_IFREG as bindings::umode_t;
+        const S_IFBLK = bindings::S_IFBLK as bindings::umode_t;
+        const S_IFDIR = bindings::S_IFDIR as bindings::umode_t;
+        const S_IFCHR = bindings::S_IFCHR as bindings::umode_t;
+        const S_IFIFO = bindings::S_IFIFO as bindings::umode_t;
+        const S_ISUID = bindings::S_ISUID as bindings::umode_t;
+        const S_ISGID = bindings::S_ISGID as bindings::umode_t;
+        const S_ISVTX = bindings::S_ISVTX as bindings::umode_t;
+        const S_IRWXU = bindings::S_IRWXU as bindings::umode_t;
+        const S_IRUSR = bindings::S_IRUSR as bindings::umode_t;
+        const S_IWUSR = bindings::S_IWUSR as bindings::umode_t;
+        const S_IXUSR = bindings::S_IXUSR as bindings::umode_t;
+        const S_IRWXG = bindings::S_IRWXG as bindings::umode_t;
+        const S_IRGRP = bindings::S_IRGRP as bindings::umode_t;
+        const S_IWGRP = bindings::S_IWGRP as bindings::umode_t;
+        const S_IXGRP = bindings::S_IXGRP as bindings::umode_t;
+        const S_IRWXO = bindings::S_IRWXO as bindings::umode_t;
+        const S_IROTH = bindings::S_IROTH as bindings::umode_t;
+        const S_IWOTH = bindings::S_IWOTH as bindings::umode_t;
+        const S_IXOTH = bindings::S_IXOTH as bindings::umode_t;
+        const S_IRWXUGO = bindings::S_IRWXUGO as bindings::umode_t;
+        const S_IALLUGO = bindings::S_IALLUGO as bindings::umode_t;
+        const S_IRUGO = bindings::S_IRUGO as bindings::umode_t;
+        const S_IWUGO = bindings::S_IWUGO as bindings::umode_t;
+        const S_IXUGO = bindings::S_IXUGO as bindings::umode_t;
+    }
+}
 
 impl Mode {
-    pub fn from_int(m: u16) -> Mode {
-        Mode(m)
+    /// Creates a mode from its numeric value, usually written in octal,
+    /// e.g. `Mode::from_int(0o644)`.
+    pub const fn from_int(m: u16) -> Mode {
+        Mode::from_bits_truncate(m)
     }
 
     pub fn as_int(&self) -> u16 {
-        self.0
+        self.bits()
     }
 }
 
@@ -28,6 +67,36 @@ impl CStr<'_> {
     pub const unsafe fn new_unchecked(data: &str) -> CStr {
         CStr(data)
     }
+
+    /// Creates a new CStr from `bytes`, which must be valid UTF-8 and end
+    /// with its only NUL byte.
+    ///
+    /// Returns EINVAL otherwise.
+    pub fn from_bytes_with_nul(bytes: &[u8]) -> KernelResult<CStr> {
+        let s = str::from_utf8(bytes)?;
+        if !CStr::is_valid(s) {
+            return Err(Error::EINVAL);
+        }
+        Ok(CStr(s))
+    }
+
+    /// Returns whether `s` ends with its only NUL byte. Used by [`cstr!`] to
+    /// check literals at compile time.
+    #[doc(hidden)]
+    pub const fn is_valid(s: &str) -> bool {
+        let bytes = s.as_bytes();
+        if bytes.is_empty() || bytes[bytes.len() - 1] != 0 {
+            return false;
+        }
+        let mut i = 0;
+        while i < bytes.len() - 1 {
+            if bytes[i] == 0 {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
 }
 
 impl Deref for CStr<'_> {
@@ -38,19 +107,107 @@ impl Deref for CStr<'_> {
     }
 }
 
-/// Creates a new `CStr` from a string literal. The string literal should not contain any NUL
-/// bytes. Example usage:
+/// An owned string that is guaranteed to have exactly one NUL byte, which is
+/// at the end. Allocated with the kernel allocator; see [`kformat!`] to
+/// build one.
+pub struct CString {
+    buf: String,
+}
+
+impl CString {
+    /// Copies `s` into a new `CString`.
+    ///
+    /// Returns ENOMEM if the allocation fails.
+    pub fn from_cstr(s: CStr) -> KernelResult<CString> {
+        let mut buf = String::new();
+        buf.try_reserve_exact(s.len()).map_err(|_| Error::ENOMEM)?;
+        buf.push_str(&s);
+        Ok(CString { buf })
+    }
+
+    /// Formats `args` into a new `CString`. Usually called through
+    /// [`kformat!`].
+    ///
+    /// Returns ENOMEM if the allocation fails, and EINVAL if the formatted
+    /// string contains a NUL byte.
+    pub fn from_fmt(args: fmt::Arguments) -> KernelResult<CString> {
+        let mut writer = FallibleWriter {
+            buf: String::new(),
+            failed: false,
+        };
+        if fmt::write(&mut writer, args).is_err() {
+            return Err(if writer.failed {
+                Error::ENOMEM
+            } else {
+                Error::EINVAL
+            });
+        }
+        fmt::Write::write_str(&mut writer, "\x00").map_err(|_| Error::ENOMEM)?;
+        if !CStr::is_valid(&writer.buf) {
+            return Err(Error::EINVAL);
+        }
+        Ok(CString { buf: writer.buf })
+    }
+
+    /// Borrows this string as a [`CStr`].
+    pub fn as_cstr(&self) -> CStr {
+        CStr(&self.buf)
+    }
+}
+
+impl Deref for CString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.buf
+    }
+}
+
+// A `fmt::Write` that reports allocation failures instead of aborting.
+struct FallibleWriter {
+    buf: String,
+    failed: bool,
+}
+
+impl fmt::Write for FallibleWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.buf.try_reserve(s.len()).is_err() {
+            self.failed = true;
+            return Err(fmt::Error);
+        }
+        self.buf.push_str(s);
+        Ok(())
+    }
+}
+
+/// Creates a new `CStr` from a string literal. The string literal must not
+/// contain any NUL bytes, which is checked at compile time. Example usage:
 /// ```
 /// const MY_CSTR: CStr<'static> = cstr!("My awesome CStr!");
 /// ```
 #[macro_export]
 macro_rules! cstr {
     ($str:expr) => {{
-        let s = concat!($str, "\x00");
-        unsafe { $crate::CStr::new_unchecked(s) }
+        const S: &str = concat!($str, "\x00");
+        // Fails to compile if the literal contains a NUL byte.
+        const _: [(); 1] = [(); $crate::CStr::is_valid(S) as usize];
+        unsafe { $crate::CStr::new_unchecked(S) }
     }};
 }
 
+/// Creates a new [`CString`] from a format string, like `format!`. Returns
+/// a `KernelResult<CString>`, which is an error if allocation fails or the
+/// result contains a NUL byte. Example usage:
+/// ```
+/// let name = kformat!("mydev{}", 3)?;
+/// ```
+#[macro_export]
+macro_rules! kformat {
+    ($($arg:tt)*) => {
+        $crate::CString::from_fmt(format_args!($($arg)*))
+    };
+}
+
 pub trait FromRaw<T> {
     unsafe fn from_raw(raw: *mut T) -> Self;
 }
@@ -1,6 +1,19 @@
-use bitflags::bitflags;
+use alloc::sync::Arc;
+use core::convert::TryInto;
+use core::{marker, mem};
+
 use crate::bindings;
+use crate::c_types;
+use crate::error::{Error, KernelResult};
+use crate::file_operations::Context;
+use crate::page::Page;
 use crate::types::FromRaw;
+use bitflags::bitflags;
+
+extern "C" {
+    fn pgprot_noncached_helper(prot: bindings::pgprot_t) -> bindings::pgprot_t;
+    fn pgprot_writecombine_helper(prot: bindings::pgprot_t) -> bindings::pgprot_t;
+}
 
 bitflags! {
     pub struct VMFlags: u64 {
@@ -48,6 +61,9 @@ bitflags! {
 
 pub struct VMA {
     raw: *mut bindings::vm_area_struct,
+    // The operations passed to `set_operations`, which are only installed
+    // once `mmap` has succeeded.
+    ops: Option<(Context, &'static bindings::vm_operations_struct)>,
 }
 
 impl VMA {
@@ -78,12 +94,248 @@ impl VMA {
     pub fn offset(&self) -> u64 {
         unsafe { (*self.raw).vm_pgoff }
     }
+
+    pub fn page_prot(&self) -> PageProtection {
+        PageProtection(unsafe { (*self.raw).vm_page_prot })
+    }
+
+    pub fn set_page_prot(&mut self, prot: PageProtection) {
+        unsafe {
+            (*self.raw).vm_page_prot = prot.0;
+        }
+    }
+
+    /// Maps `size` bytes of physical memory starting at page frame `pfn` into
+    /// this VMA at `addr`. Corresponds to `remap_pfn_range`.
+    ///
+    /// Memory owned by the module is better mapped with
+    /// [`VMA::insert_page`] or [`VMA::set_operations`], which are safe.
+    ///
+    /// # Safety
+    ///
+    /// The page frames `pfn..pfn + size / PAGE_SIZE` must belong to the
+    /// module (e.g. memory of its device), and must stay valid and unused
+    /// for anything else for as long as they are mapped, which may be past
+    /// the lifetime of the file.
+    pub unsafe fn remap_pfn_range(
+        &mut self,
+        addr: u64,
+        pfn: u64,
+        size: usize,
+        prot: PageProtection,
+    ) -> KernelResult<()> {
+        if addr < self.start()
+            || addr
+                .checked_add(size as u64)
+                .map_or(true, |end| end > self.end())
+        {
+            return Err(Error::EINVAL);
+        }
+
+        let res = bindings::remap_pfn_range(self.raw, addr, pfn, size.try_into()?, prot.0);
+
+        if res != 0 {
+            return Err(Error::from_kernel_errno(res));
+        }
+
+        Ok(())
+    }
+
+    /// Inserts `page` into this VMA at `addr`. The VMA takes its own
+    /// reference to the page, so `page` may be dropped afterwards. Corresponds
+    /// to `vm_insert_page`.
+    pub fn insert_page(&mut self, addr: u64, page: &Page) -> KernelResult<()> {
+        let res = unsafe { bindings::vm_insert_page(self.raw, addr, page.raw()) };
+
+        if res != 0 {
+            return Err(Error::from_kernel_errno(res));
+        }
+
+        Ok(())
+    }
+
+    /// Sets `ops` as the `vm_operations_struct` of this VMA, from
+    /// [`FileOperations::mmap`]. They are installed when `mmap` returns
+    /// successfully, and replace those of any earlier call; if `mmap` fails,
+    /// they are dropped.
+    ///
+    /// Every VMA derived from this one (e.g. when it is split or the process
+    /// forks) shares `ops`, which is dropped when the last of them is closed.
+    ///
+    /// [`FileOperations::mmap`]: crate::file_operations::FileOperations::mmap
+    pub fn set_operations<T: VmOperations>(&mut self, ops: Arc<T>) {
+        self.ops = Some((Context::new(ops), &VmOperationsVtable::<T>::VTABLE));
+    }
+
+    /// Installs the operations passed to [`VMA::set_operations`], handing
+    /// their reference over to the VMA, which releases it in `close`.
+    pub(crate) fn install_operations(&mut self) {
+        if let Some((ops, vtable)) = self.ops.take() {
+            unsafe {
+                (*self.raw).vm_private_data = ops.ptr as *mut c_types::c_void;
+                (*self.raw).vm_ops = vtable;
+            }
+            mem::forget(ops);
+        }
+    }
+}
+
+/// The page protection bits of a VMA. Corresponds to the kernel's `pgprot_t`.
+#[derive(Clone, Copy)]
+pub struct PageProtection(bindings::pgprot_t);
+
+impl PageProtection {
+    /// Returns these protection bits with caching disabled. Corresponds to
+    /// `pgprot_noncached`.
+    pub fn noncached(self) -> Self {
+        PageProtection(unsafe { pgprot_noncached_helper(self.0) })
+    }
+
+    /// Returns these protection bits with write-combining enabled.
+    /// Corresponds to `pgprot_writecombine`.
+    pub fn writecombine(self) -> Self {
+        PageProtection(unsafe { pgprot_writecombine_helper(self.0) })
+    }
+}
+
+/// A page fault within a VMA. Corresponds to the kernel's `struct vm_fault`.
+pub struct VmFault {
+    raw: *mut bindings::vm_fault,
+}
+
+impl VmFault {
+    /// Returns the offset of the faulting page within the mapped file, in
+    /// pages.
+    pub fn pgoff(&self) -> u64 {
+        unsafe { (*self.raw).pgoff }
+    }
+
+    /// Returns the faulting virtual address.
+    #[cfg(kernel_4_10_0_or_greater)]
+    pub fn address(&self) -> u64 {
+        unsafe { (*self.raw).address }
+    }
+
+    /// Returns the faulting virtual address.
+    #[cfg(not(kernel_4_10_0_or_greater))]
+    pub fn address(&self) -> u64 {
+        unsafe { (*self.raw).virtual_address as u64 }
+    }
+
+    /// Resolves the fault by mapping `page`. The kernel takes its own
+    /// reference to the page.
+    pub fn set_page(&mut self, page: &Page) {
+        page.get();
+
+        unsafe {
+            (*self.raw).page = page.raw();
+        }
+    }
+}
+
+#[cfg(kernel_4_17_0_or_greater)]
+type FaultResult = bindings::vm_fault_t;
+
+#[cfg(not(kernel_4_17_0_or_greater))]
+type FaultResult = c_types::c_int;
+
+unsafe extern "C" fn vm_open_callback<T: VmOperations>(vma: *mut bindings::vm_area_struct) {
+    // The new VMA shares the operations of the one it was copied from, so
+    // take another reference on them.
+    let ops = mem::ManuallyDrop::new(Arc::from_raw((*vma).vm_private_data as *const T));
+    mem::forget(Arc::clone(&ops));
+
+    ops.open(&VMA::from_raw(vma));
+}
+
+unsafe extern "C" fn vm_close_callback<T: VmOperations>(vma: *mut bindings::vm_area_struct) {
+    let ops = Arc::from_raw((*vma).vm_private_data as *const T);
+
+    ops.close(&VMA::from_raw(vma));
+}
+
+unsafe fn handle_fault<T: VmOperations>(
+    vma: *mut bindings::vm_area_struct,
+    vmf: *mut bindings::vm_fault,
+) -> FaultResult {
+    let ops = &*((*vma).vm_private_data as *const T);
+    let mut fault = VmFault { raw: vmf };
+
+    let result = match ops.fault(&VMA::from_raw(vma), &mut fault) {
+        Ok(()) if !(*vmf).page.is_null() => 0,
+        Ok(()) => bindings::BINDINGS_VM_FAULT_NOPAGE,
+        Err(e) if e.to_kernel_errno() == Error::ENOMEM.to_kernel_errno() => {
+            bindings::BINDINGS_VM_FAULT_OOM
+        }
+        Err(_) => bindings::BINDINGS_VM_FAULT_SIGBUS,
+    };
+
+    result as FaultResult
+}
+
+#[cfg(kernel_4_11_0_or_greater)]
+unsafe extern "C" fn vm_fault_callback<T: VmOperations>(
+    vmf: *mut bindings::vm_fault,
+) -> FaultResult {
+    handle_fault::<T>((*vmf).vma, vmf)
+}
+
+#[cfg(not(kernel_4_11_0_or_greater))]
+unsafe extern "C" fn vm_fault_callback<T: VmOperations>(
+    vma: *mut bindings::vm_area_struct,
+    vmf: *mut bindings::vm_fault,
+) -> FaultResult {
+    handle_fault::<T>(vma, vmf)
+}
+
+struct VmOperationsVtable<T>(marker::PhantomData<T>);
+
+impl<T: VmOperations> VmOperationsVtable<T> {
+    // The layout of `vm_operations_struct` changes with nearly every kernel
+    // release, so start from an all-`None` table rather than listing every
+    // field.
+    const VTABLE: bindings::vm_operations_struct = bindings::vm_operations_struct {
+        open: Some(vm_open_callback::<T>),
+        close: Some(vm_close_callback::<T>),
+        fault: Some(vm_fault_callback::<T>),
+
+        ..unsafe {
+            mem::transmute::<
+                [u8; mem::size_of::<bindings::vm_operations_struct>()],
+                bindings::vm_operations_struct,
+            >([0; mem::size_of::<bindings::vm_operations_struct>()])
+        }
+    };
+}
+
+/// `VmOperations` corresponds to the kernel's `struct vm_operations_struct`.
+/// Install an implementation on a VMA with [`VMA::set_operations`]. Since
+/// every VMA derived from the original mapping shares the same instance,
+/// your type must be `Sync` and `Send`.
+pub trait VmOperations: Sync + Send + Sized {
+    /// Called when a new VMA is derived from one using these operations.
+    /// Corresponds to the `open` function pointer in
+    /// `struct vm_operations_struct`.
+    fn open(&self, _vma: &VMA) {}
+
+    /// Called when a VMA using these operations is unmapped. Corresponds to
+    /// the `close` function pointer in `struct vm_operations_struct`.
+    fn close(&self, _vma: &VMA) {}
+
+    /// Resolves a page fault within `vma`, either by calling
+    /// [`VmFault::set_page`] or by inserting the page into the VMA directly.
+    /// Corresponds to the `fault` function pointer in
+    /// `struct vm_operations_struct`.
+    ///
+    /// `ENOMEM` is reported as `VM_FAULT_OOM`, any other error as
+    /// `VM_FAULT_SIGBUS`.
+    fn fault(&self, _vma: &VMA, _fault: &mut VmFault) -> KernelResult<()> {
+        Err(Error::EFAULT)
+    }
 }
 
 impl FromRaw<bindings::vm_area_struct> for VMA {
     unsafe fn from_raw(raw: *mut bindings::vm_area_struct) -> Self {
-        Self {
-            raw,
-        }
+        Self { raw, ops: None }
     }
 }
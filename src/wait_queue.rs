@@ -0,0 +1,92 @@
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::mem;
+
+use crate::bindings;
+use crate::c_types;
+use crate::error::{Error, KernelResult};
+
+extern "C" {
+    fn init_waitqueue_head_helper(wq: *mut bindings::wait_queue_head_t);
+    fn wake_up_interruptible_helper(wq: *mut bindings::wait_queue_head_t);
+    fn wake_up_interruptible_all_helper(wq: *mut bindings::wait_queue_head_t);
+    fn wait_event_interruptible_helper(
+        wq: *mut bindings::wait_queue_head_t,
+        condition: unsafe extern "C" fn(*mut c_types::c_void) -> bool,
+        data: *mut c_types::c_void,
+    ) -> c_types::c_int;
+}
+
+unsafe extern "C" fn condition_callback<F: FnMut() -> bool>(data: *mut c_types::c_void) -> bool {
+    let condition = &mut *(data as *mut F);
+    condition()
+}
+
+/// A queue of tasks waiting for some condition to become true. Corresponds
+/// to the kernel's `wait_queue_head_t`.
+///
+/// The kernel links waiting tasks into the queue head, so it is kept on the
+/// heap to ensure it never moves once initialized.
+pub struct WaitQueue {
+    head: Box<UnsafeCell<bindings::wait_queue_head_t>>,
+}
+
+// This is safe because all accesses to the wait queue are serialized by the
+// spinlock embedded in `wait_queue_head_t`.
+unsafe impl Send for WaitQueue {}
+unsafe impl Sync for WaitQueue {}
+
+#[allow(clippy::new_without_default)]
+impl WaitQueue {
+    /// Creates a new, empty wait queue.
+    pub fn new() -> Self {
+        let head = Box::new(UnsafeCell::new(unsafe { mem::zeroed() }));
+
+        unsafe {
+            init_waitqueue_head_helper(head.get());
+        }
+
+        Self { head }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut bindings::wait_queue_head_t {
+        self.head.get()
+    }
+
+    /// Puts the current task to sleep until `condition` returns `true`. The
+    /// condition is re-evaluated every time the queue is woken up.
+    /// Corresponds to `wait_event_interruptible`.
+    ///
+    /// Returns `ERESTARTSYS` if the sleep was interrupted by a signal.
+    pub fn wait_interruptible<F: FnMut() -> bool>(&self, mut condition: F) -> KernelResult<()> {
+        let res = unsafe {
+            wait_event_interruptible_helper(
+                self.as_ptr(),
+                condition_callback::<F>,
+                &mut condition as *mut F as *mut c_types::c_void,
+            )
+        };
+
+        if res != 0 {
+            return Err(Error::from_kernel_errno(res));
+        }
+
+        Ok(())
+    }
+
+    /// Wakes up the tasks sleeping interruptibly on this queue, including at
+    /// most one exclusive waiter. Corresponds to `wake_up_interruptible`.
+    pub fn wake_up_interruptible(&self) {
+        unsafe {
+            wake_up_interruptible_helper(self.as_ptr());
+        }
+    }
+
+    /// Wakes up all tasks sleeping interruptibly on this queue. Corresponds
+    /// to `wake_up_interruptible_all`.
+    pub fn wake_up_interruptible_all(&self) {
+        unsafe {
+            wake_up_interruptible_all_helper(self.as_ptr());
+        }
+    }
+}
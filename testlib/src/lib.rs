@@ -55,6 +55,13 @@ pub fn assert_dmesg_contains(msgs: &[&[u8]]) {
     }
 }
 
+pub fn assert_dmesg_not_contains(msg: &[u8]) {
+    let output = Command::new("dmesg").output().unwrap();
+    assert!(output.status.success());
+    let mut lines = output.stdout.split(|x| *x == b'\n');
+    assert!(!lines.any(|l| l.ends_with(msg)));
+}
+
 pub fn get_device_major_number(name: &str) -> libc::dev_t {
     let devices = fs::read_to_string("/proc/devices").unwrap();
     let dev_no_line = devices.lines().find(|l| l.ends_with(name)).unwrap();
@@ -2,15 +2,33 @@
 
 extern crate alloc;
 
+use alloc::format;
 use alloc::string::ToString;
-use core::sync::atomic::{AtomicUsize, Ordering};
+use alloc::sync::Arc;
+use alloc::vec;
+use core::cmp;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
+use linux_kernel_module::file_operations::{FileFlags, FileMode, Inode, PollFlags, PollTable};
+use linux_kernel_module::ioctl::{Command, Ioctl};
+use linux_kernel_module::iov_iter::IovIter;
+use linux_kernel_module::page::Page;
+use linux_kernel_module::vma::{VmFault, VmOperations, VMA};
+use linux_kernel_module::wait_queue::WaitQueue;
 use linux_kernel_module::{self, cstr};
 
 struct CycleFile;
 
 impl linux_kernel_module::file_operations::FileOperations for CycleFile {
-    fn open() -> linux_kernel_module::KernelResult<Self> {
+    type Context = ();
+
+    linux_kernel_module::declare_file_operations!(read);
+
+    fn open(
+        _context: &Arc<()>,
+        _inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
         Ok(CycleFile)
     }
 
@@ -35,7 +53,15 @@ impl linux_kernel_module::file_operations::FileOperations for CycleFile {
 struct SeekFile;
 
 impl linux_kernel_module::file_operations::FileOperations for SeekFile {
-    fn open() -> linux_kernel_module::KernelResult<Self> {
+    type Context = ();
+
+    linux_kernel_module::declare_file_operations!(seek);
+
+    fn open(
+        _context: &Arc<()>,
+        _inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
         Ok(SeekFile)
     }
 
@@ -53,7 +79,15 @@ struct WriteFile {
 }
 
 impl linux_kernel_module::file_operations::FileOperations for WriteFile {
-    fn open() -> linux_kernel_module::KernelResult<Self> {
+    type Context = ();
+
+    linux_kernel_module::declare_file_operations!(read, write);
+
+    fn open(
+        _context: &Arc<()>,
+        _inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
         Ok(WriteFile {
             written: AtomicUsize::new(0),
         })
@@ -65,7 +99,7 @@ impl linux_kernel_module::file_operations::FileOperations for WriteFile {
         buf: &mut linux_kernel_module::user_ptr::UserSlicePtrWriter,
         _offset: u64,
     ) -> linux_kernel_module::KernelResult<()> {
-        let val = this.written.load(Ordering::SeqCst).to_string();
+        let val = self.written.load(Ordering::SeqCst).to_string();
         buf.write(val.as_bytes())?;
         Ok(())
     }
@@ -77,7 +111,294 @@ impl linux_kernel_module::file_operations::FileOperations for WriteFile {
         _offset: u64,
     ) -> linux_kernel_module::KernelResult<()> {
         let data = buf.read_all()?;
-        this.written.fetch_add(data.len(), Ordering::SeqCst);
+        self.written.fetch_add(data.len(), Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+struct PollFile {
+    queue: WaitQueue,
+    pending: AtomicUsize,
+}
+
+impl linux_kernel_module::file_operations::FileOperations for PollFile {
+    type Context = ();
+
+    linux_kernel_module::declare_file_operations!(read, write, poll);
+
+    fn open(
+        _context: &Arc<()>,
+        _inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        Ok(PollFile {
+            queue: WaitQueue::new(),
+            pending: AtomicUsize::new(0),
+        })
+    }
+
+    fn read(
+        &self,
+        file: &linux_kernel_module::file_operations::File,
+        buf: &mut linux_kernel_module::user_ptr::UserSlicePtrWriter,
+        _offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        if file.flags().contains(FileFlags::NONBLOCK) {
+            if self.pending.load(Ordering::SeqCst) == 0 {
+                return Err(linux_kernel_module::Error::EAGAIN);
+            }
+        } else {
+            self.queue
+                .wait_interruptible(|| self.pending.load(Ordering::SeqCst) != 0)?;
+        }
+        let val = self.pending.swap(0, Ordering::SeqCst).to_string();
+        buf.write(val.as_bytes())?;
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        _file: &linux_kernel_module::file_operations::File,
+        buf: &mut linux_kernel_module::user_ptr::UserSlicePtrReader,
+        _offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        let data = buf.read_all()?;
+        self.pending.fetch_add(data.len(), Ordering::SeqCst);
+        self.queue.wake_up_interruptible();
+        Ok(())
+    }
+
+    fn poll(
+        &self,
+        file: &linux_kernel_module::file_operations::File,
+        table: &PollTable,
+    ) -> linux_kernel_module::KernelResult<PollFlags> {
+        table.wait(file, &self.queue);
+        let mut flags = PollFlags::OUT | PollFlags::WRNORM;
+        if self.pending.load(Ordering::SeqCst) != 0 {
+            flags |= PollFlags::IN | PollFlags::RDNORM;
+        }
+        Ok(flags)
+    }
+}
+
+struct SharedPage {
+    page: Page,
+}
+
+impl VmOperations for SharedPage {
+    fn fault(&self, _vma: &VMA, fault: &mut VmFault) -> linux_kernel_module::KernelResult<()> {
+        if fault.pgoff() != 0 {
+            return Err(linux_kernel_module::Error::EFAULT);
+        }
+        fault.set_page(&self.page);
+        Ok(())
+    }
+}
+
+struct MmapFile {
+    shared: Arc<SharedPage>,
+}
+
+impl linux_kernel_module::file_operations::FileOperations for MmapFile {
+    type Context = ();
+
+    linux_kernel_module::declare_file_operations!(mmap);
+
+    fn open(
+        _context: &Arc<()>,
+        _inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        let page = Page::alloc()?;
+        page.write(0, b"123456789")?;
+        Ok(MmapFile {
+            shared: Arc::new(SharedPage { page }),
+        })
+    }
+
+    fn mmap(
+        &self,
+        _file: &linux_kernel_module::file_operations::File,
+        vma: &mut VMA,
+    ) -> linux_kernel_module::KernelResult<()> {
+        vma.set_operations(self.shared.clone());
+        Ok(())
+    }
+}
+
+const IOCTL_GET: Command<u64> = Command::ior(b'c' as u32, 0);
+const IOCTL_SET: Command<u64> = Command::iow(b'c' as u32, 1);
+const IOCTL_ADD: Command<u64> = Command::iowr(b'c' as u32, 2);
+
+struct IoctlFile {
+    value: AtomicU64,
+}
+
+impl linux_kernel_module::file_operations::FileOperations for IoctlFile {
+    type Context = ();
+
+    linux_kernel_module::declare_file_operations!(ioctl, compat_ioctl);
+
+    fn open(
+        _context: &Arc<()>,
+        _inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        Ok(IoctlFile {
+            value: AtomicU64::new(0),
+        })
+    }
+
+    fn unlocked_ioctl(
+        &self,
+        _file: &linux_kernel_module::file_operations::File,
+        num: Ioctl,
+        param: u64,
+    ) -> linux_kernel_module::KernelResult<u64> {
+        if let Some(ret) = IOCTL_GET.dispatch(num, param, |v| {
+            *v = self.value.load(Ordering::SeqCst);
+            Ok(0)
+        }) {
+            return ret;
+        }
+        if let Some(ret) = IOCTL_SET.dispatch(num, param, |v| {
+            self.value.store(*v, Ordering::SeqCst);
+            Ok(0)
+        }) {
+            return ret;
+        }
+        if let Some(ret) = IOCTL_ADD.dispatch(num, param, |v| {
+            *v += self.value.fetch_add(*v, Ordering::SeqCst);
+            Ok(0)
+        }) {
+            return ret;
+        }
+        Err(linux_kernel_module::Error::ENOTTY)
+    }
+}
+
+struct ContextFile {
+    name: &'static str,
+    minor: u32,
+}
+
+impl linux_kernel_module::file_operations::FileOperations for ContextFile {
+    type Context = &'static str;
+
+    linux_kernel_module::declare_file_operations!(read);
+
+    fn open(
+        context: &Arc<&'static str>,
+        inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        Ok(ContextFile {
+            name: **context,
+            minor: inode.device_number().minor(),
+        })
+    }
+
+    fn read(
+        &self,
+        _file: &linux_kernel_module::file_operations::File,
+        buf: &mut linux_kernel_module::user_ptr::UserSlicePtrWriter,
+        offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        if offset != 0 {
+            return Ok(());
+        }
+        let val = format!("{}:{}", self.name, self.minor);
+        buf.write(val.as_bytes())?;
+        Ok(())
+    }
+}
+
+struct MetadataFile;
+
+impl linux_kernel_module::file_operations::FileOperations for MetadataFile {
+    type Context = ();
+
+    linux_kernel_module::declare_file_operations!(read);
+
+    fn open(
+        _context: &Arc<()>,
+        _inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        Ok(MetadataFile)
+    }
+
+    fn read(
+        &self,
+        file: &linux_kernel_module::file_operations::File,
+        buf: &mut linux_kernel_module::user_ptr::UserSlicePtrWriter,
+        offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        if offset != 0 {
+            return Ok(());
+        }
+        let mut path = [0; 256];
+        let path = file.path(&mut path)?;
+        let val = format!(
+            "{} {} {} {} {}",
+            file.mode().contains(FileMode::READ),
+            file.mode().contains(FileMode::WRITE),
+            file.flags().contains(FileFlags::APPEND),
+            file.cred().uid(),
+            core::str::from_utf8(path).map_err(|_| linux_kernel_module::Error::EINVAL)?,
+        );
+        buf.write(val.as_bytes())?;
+        Ok(())
+    }
+}
+
+struct IterFile {
+    page: Page,
+    len: AtomicUsize,
+}
+
+impl linux_kernel_module::file_operations::FileOperations for IterFile {
+    type Context = ();
+
+    linux_kernel_module::declare_file_operations!(read_iter, write_iter);
+
+    fn open(
+        _context: &Arc<()>,
+        _inode: &Inode,
+        _file: &linux_kernel_module::file_operations::File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        Ok(IterFile {
+            page: Page::alloc()?,
+            len: AtomicUsize::new(0),
+        })
+    }
+
+    fn read_iter(
+        &self,
+        _file: &linux_kernel_module::file_operations::File,
+        buf: &mut IovIter,
+        offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        let len = self.len.load(Ordering::SeqCst);
+        let offset = cmp::min(offset, len as u64) as usize;
+        let mut data = vec![0; cmp::min(len - offset, buf.len())];
+        self.page.read(offset, &mut data)?;
+        buf.write(&data)
+    }
+
+    fn write_iter(
+        &self,
+        _file: &linux_kernel_module::file_operations::File,
+        buf: &mut IovIter,
+        offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        let data = buf.read_all()?;
+        let offset = offset as usize;
+        self.page
+            .write(offset, &data)
+            .map_err(|_| linux_kernel_module::Error::ENOSPC)?;
+        self.len.fetch_max(offset + data.len(), Ordering::SeqCst);
         Ok(())
     }
 }
@@ -89,10 +410,18 @@ struct ChrdevTestModule {
 impl linux_kernel_module::KernelModule for ChrdevTestModule {
     fn init() -> linux_kernel_module::KernelResult<Self> {
         let chrdev_registration =
-            linux_kernel_module::chrdev::builder(cstr!("chrdev-tests"), 0..3)?
-                .register_device::<CycleFile>()
-                .register_device::<SeekFile>()
-                .register_device::<WriteFile>()
+            linux_kernel_module::chrdev::builder(cstr!("chrdev-tests"), 0..10)?
+                .register_device::<CycleFile>(Arc::new(()))
+                .register_device::<SeekFile>(Arc::new(()))
+                .register_device::<WriteFile>(Arc::new(()))
+                .register_device::<PollFile>(Arc::new(()))
+                .register_device::<MmapFile>(Arc::new(()))
+                .register_device::<IoctlFile>(Arc::new(()))
+                .register_device::<ContextFile>(Arc::new("first"))
+                .register_device::<ContextFile>(Arc::new("second"))
+                .register_device::<MetadataFile>(Arc::new(()))
+                .register_device::<IterFile>(Arc::new(()))
+                .create_nodes(cstr!("chrdev-tests"), "chrdev-tests")
                 .build()?;
         Ok(ChrdevTestModule {
             _chrdev_registration: chrdev_registration,
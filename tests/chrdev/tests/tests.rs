@@ -1,6 +1,9 @@
 use std::fs;
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{IoSlice, IoSliceMut, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
 use std::os::unix::prelude::FileExt;
+use std::path::Path;
 
 use kernel_module_testlib::*;
 
@@ -8,6 +11,33 @@ const DEVICE_NAME: &'static str = "chrdev-tests";
 const READ_FILE_MINOR: libc::dev_t = 0;
 const SEEK_FILE_MINOR: libc::dev_t = 1;
 const WRITE_FILE_MINOR: libc::dev_t = 2;
+const POLL_FILE_MINOR: libc::dev_t = 3;
+const MMAP_FILE_MINOR: libc::dev_t = 4;
+const IOCTL_FILE_MINOR: libc::dev_t = 5;
+const FIRST_CONTEXT_FILE_MINOR: libc::dev_t = 6;
+const SECOND_CONTEXT_FILE_MINOR: libc::dev_t = 7;
+const METADATA_FILE_MINOR: libc::dev_t = 8;
+const ITER_FILE_MINOR: libc::dev_t = 9;
+
+const fn ioctl_number(dir: libc::c_ulong, num: libc::c_ulong, size: usize) -> libc::c_ulong {
+    dir << 30 | (size as libc::c_ulong) << 16 | (b'c' as libc::c_ulong) << 8 | num
+}
+
+const IOCTL_GET: libc::c_ulong = ioctl_number(2, 0, std::mem::size_of::<u64>());
+const IOCTL_SET: libc::c_ulong = ioctl_number(1, 1, std::mem::size_of::<u64>());
+const IOCTL_ADD: libc::c_ulong = ioctl_number(3, 2, std::mem::size_of::<u64>());
+const IOCTL_GET_U32: libc::c_ulong = ioctl_number(2, 0, std::mem::size_of::<u32>());
+
+fn poll_revents(f: &fs::File, events: libc::c_short) -> libc::c_short {
+    let mut fds = [libc::pollfd {
+        fd: f.as_raw_fd(),
+        events,
+        revents: 0,
+    }];
+    let res = unsafe { libc::poll(fds.as_mut_ptr(), 1, 0) };
+    assert!(res >= 0);
+    fds[0].revents
+}
 
 #[test]
 fn test_mknod() {
@@ -171,3 +201,370 @@ fn test_write() {
         assert_eq!(&buf, b"8");
     })
 }
+
+#[test]
+fn test_poll() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, POLL_FILE_MINOR);
+
+        let mut f = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&p)
+            .unwrap();
+        assert_eq!(
+            poll_revents(&f, libc::POLLIN | libc::POLLOUT),
+            libc::POLLOUT
+        );
+
+        f.write(&[1, 2, 3]).unwrap();
+        assert_eq!(
+            poll_revents(&f, libc::POLLIN | libc::POLLOUT),
+            libc::POLLIN | libc::POLLOUT
+        );
+
+        let mut buf = [0; 1];
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"3");
+        assert_eq!(poll_revents(&f, libc::POLLIN), 0);
+    })
+}
+
+#[test]
+fn test_read_nonblock() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, POLL_FILE_MINOR);
+
+        let mut f = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&p)
+            .unwrap();
+        let mut buf = [0; 1];
+        assert_eq!(
+            f.read(&mut buf).unwrap_err().raw_os_error().unwrap(),
+            libc::EAGAIN
+        );
+    })
+}
+
+#[test]
+fn test_mmap() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, MMAP_FILE_MINOR);
+
+        let f = fs::File::open(&p).unwrap();
+        let len = 4096;
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                f.as_raw_fd(),
+                0,
+            )
+        };
+        assert_ne!(addr, libc::MAP_FAILED);
+        let data = unsafe { std::slice::from_raw_parts(addr as *const u8, 12) };
+        assert_eq!(data, b"123456789\0\0\0");
+        unsafe { libc::munmap(addr, len) };
+    })
+}
+
+#[test]
+fn test_mmap_unimplemented() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, READ_FILE_MINOR);
+
+        let f = fs::File::open(&p).unwrap();
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                4096,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                f.as_raw_fd(),
+                0,
+            )
+        };
+        assert_eq!(addr, libc::MAP_FAILED);
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error().unwrap(),
+            libc::ENODEV
+        );
+    })
+}
+
+#[test]
+fn test_ioctl() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, IOCTL_FILE_MINOR);
+
+        let f = fs::File::open(&p).unwrap();
+        let mut value: u64 = 42;
+        assert_eq!(
+            unsafe { libc::ioctl(f.as_raw_fd(), IOCTL_SET, &mut value) },
+            0
+        );
+
+        value = 0;
+        assert_eq!(
+            unsafe { libc::ioctl(f.as_raw_fd(), IOCTL_GET, &mut value) },
+            0
+        );
+        assert_eq!(value, 42);
+
+        value = 8;
+        assert_eq!(
+            unsafe { libc::ioctl(f.as_raw_fd(), IOCTL_ADD, &mut value) },
+            0
+        );
+        assert_eq!(value, 50);
+
+        assert_eq!(
+            unsafe { libc::ioctl(f.as_raw_fd(), IOCTL_GET, std::ptr::null_mut::<u64>()) },
+            -1
+        );
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error().unwrap(),
+            libc::EFAULT
+        );
+
+        let mut small: u32 = 0;
+        assert_eq!(
+            unsafe { libc::ioctl(f.as_raw_fd(), IOCTL_GET_U32, &mut small) },
+            -1
+        );
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error().unwrap(),
+            libc::EINVAL
+        );
+    })
+}
+
+#[test]
+fn test_ioctl_unimplemented() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, READ_FILE_MINOR);
+
+        let f = fs::File::open(&p).unwrap();
+        let mut value: u64 = 0;
+        assert_eq!(
+            unsafe { libc::ioctl(f.as_raw_fd(), IOCTL_GET, &mut value) },
+            -1
+        );
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error().unwrap(),
+            libc::ENOTTY
+        );
+    })
+}
+
+#[test]
+fn test_readv() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, READ_FILE_MINOR);
+
+        let mut f = fs::File::open(&p).unwrap();
+        let mut a = [0; 4];
+        let mut b = [0; 8];
+        assert_eq!(
+            f.read_vectored(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)])
+                .unwrap(),
+            12
+        );
+        assert_eq!(&a, b"1234");
+        assert_eq!(&b, b"56789123");
+    })
+}
+
+#[test]
+fn test_writev() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, WRITE_FILE_MINOR);
+
+        let mut f = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&p)
+            .unwrap();
+        assert_eq!(
+            f.write_vectored(&[IoSlice::new(&[1, 2]), IoSlice::new(&[3, 4, 5])])
+                .unwrap(),
+            5
+        );
+
+        let mut buf = [0; 1];
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"5");
+    })
+}
+
+#[test]
+fn test_iter_readv_writev() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, ITER_FILE_MINOR);
+
+        let mut f = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&p)
+            .unwrap();
+        assert_eq!(
+            f.write_vectored(&[IoSlice::new(b"hello "), IoSlice::new(b"world")])
+                .unwrap(),
+            11
+        );
+
+        let mut a = [0; 4];
+        let mut b = [0; 8];
+        let iov = [
+            libc::iovec {
+                iov_base: a.as_mut_ptr() as *mut libc::c_void,
+                iov_len: a.len(),
+            },
+            libc::iovec {
+                iov_base: b.as_mut_ptr() as *mut libc::c_void,
+                iov_len: b.len(),
+            },
+        ];
+        assert_eq!(
+            unsafe { libc::preadv(f.as_raw_fd(), iov.as_ptr(), 2, 2) },
+            9
+        );
+        assert_eq!(&a, b"llo ");
+        assert_eq!(&b[..5], b"world");
+    })
+}
+
+#[test]
+fn test_iter_sendfile() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, ITER_FILE_MINOR);
+
+        let mut f = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&p)
+            .unwrap();
+        f.write_all(b"0123456789").unwrap();
+
+        let out_path = temporary_file_path();
+        let out = fs::File::create(&out_path).unwrap();
+        let mut offset: libc::off_t = 3;
+        assert_eq!(
+            unsafe { libc::sendfile(out.as_raw_fd(), f.as_raw_fd(), &mut offset, 5) },
+            5
+        );
+        assert_eq!(offset, 8);
+        assert_eq!(fs::read(&out_path).unwrap(), b"34567");
+    })
+}
+
+#[test]
+fn test_iter_splice_write() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, ITER_FILE_MINOR);
+
+        let f = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&p)
+            .unwrap();
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        assert_eq!(
+            unsafe { libc::write(fds[1], b"spliced".as_ptr() as *const libc::c_void, 7) },
+            7
+        );
+
+        let mut offset: libc::loff_t = 0;
+        assert_eq!(
+            unsafe {
+                libc::splice(
+                    fds[0],
+                    std::ptr::null_mut(),
+                    f.as_raw_fd(),
+                    &mut offset,
+                    7,
+                    0,
+                )
+            },
+            7
+        );
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+
+        let mut buf = [0; 7];
+        f.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"spliced");
+    })
+}
+
+#[test]
+fn test_open_context() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        for (minor, expected) in &[
+            (FIRST_CONTEXT_FILE_MINOR, "first:6"),
+            (SECOND_CONTEXT_FILE_MINOR, "second:7"),
+        ] {
+            let p = temporary_file_path();
+            let _u = mknod(&p, device_number, *minor);
+            assert_eq!(&fs::read_to_string(&p).unwrap(), expected);
+        }
+    })
+}
+
+#[test]
+fn test_device_nodes() {
+    with_kernel_module(|| {
+        assert!(Path::new("/sys/class/chrdev-tests").exists());
+        assert!(Path::new("/dev/chrdev-tests0").exists());
+        assert!(Path::new("/dev/chrdev-tests7").exists());
+    });
+    assert!(!Path::new("/dev/chrdev-tests6").exists());
+    assert!(!Path::new("/sys/class/chrdev-tests").exists());
+}
+
+#[test]
+fn test_file_metadata() {
+    with_kernel_module(|| {
+        let device_number = get_device_major_number(DEVICE_NAME);
+        let p = temporary_file_path();
+        let _u = mknod(&p, device_number, METADATA_FILE_MINOR);
+        let uid = unsafe { libc::getuid() };
+
+        let mut data = String::new();
+        fs::File::open(&p)
+            .unwrap()
+            .read_to_string(&mut data)
+            .unwrap();
+        assert_eq!(data, format!("true false false {} {}", uid, p.display()));
+    });
+}
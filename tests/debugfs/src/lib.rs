@@ -0,0 +1,91 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::format;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+use linux_kernel_module::debugfs::Dir;
+use linux_kernel_module::file_operations::{File, FileOperations, Inode};
+use linux_kernel_module::user_ptr::UserSlicePtrWriter;
+use linux_kernel_module::{self, cstr, Mode};
+
+static NUMBER: AtomicU32 = AtomicU32::new(42);
+static FLAGS: AtomicU32 = AtomicU32::new(0xbeef);
+static BIG_NUMBER: AtomicU64 = AtomicU64::new(1 << 40);
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static BLOB: &[u8] = b"blob contents\n";
+
+struct Greeting {
+    name: &'static str,
+}
+
+impl FileOperations for Greeting {
+    type Context = &'static str;
+
+    linux_kernel_module::declare_file_operations!(read);
+
+    fn open(
+        context: &Arc<&'static str>,
+        _inode: &Inode,
+        _file: &File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        Ok(Greeting { name: **context })
+    }
+
+    fn read(
+        &self,
+        _file: &File,
+        buf: &mut UserSlicePtrWriter,
+        offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        let data = format!("hello, {}\n", self.name);
+        if offset == 0 && buf.len() >= data.len() {
+            buf.write(data.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+struct DebugfsTestModule {
+    _dir: Dir,
+}
+
+impl linux_kernel_module::KernelModule for DebugfsTestModule {
+    fn init() -> linux_kernel_module::KernelResult<Self> {
+        let mut dir = Dir::new(cstr!("rust-debugfs-tests"))?;
+        dir.create_u32(cstr!("u32"), Mode::from_int(0o644), &NUMBER)?;
+        dir.create_x32(cstr!("x32"), Mode::from_int(0o644), &FLAGS)?;
+        dir.create_u64(cstr!("u64"), Mode::from_int(0o644), &BIG_NUMBER)?;
+        dir.create_bool(cstr!("bool"), Mode::from_int(0o644), &ENABLED)?;
+        dir.create_blob(cstr!("blob"), Mode::from_int(0o444), BLOB)?;
+        dir.create_blob(cstr!("writable-blob"), Mode::from_int(0o644), BLOB)?;
+        dir.create_file::<Greeting>(cstr!("greeting"), Mode::from_int(0o444), Arc::new("world"))?;
+
+        let sub = dir.create_dir(cstr!("sub"))?;
+        sub.create_u32(cstr!("u32"), Mode::from_int(0o644), &NUMBER)?;
+
+        Ok(DebugfsTestModule { _dir: dir })
+    }
+}
+
+impl Drop for DebugfsTestModule {
+    fn drop(&mut self) {
+        // Report the values written by the tests.
+        linux_kernel_module::println!(
+            "debugfs values: {} {:#x} {} {}",
+            NUMBER.load(Ordering::Relaxed),
+            FLAGS.load(Ordering::Relaxed),
+            BIG_NUMBER.load(Ordering::Relaxed),
+            ENABLED.load(Ordering::Relaxed)
+        );
+    }
+}
+
+linux_kernel_module::kernel_module!(
+    DebugfsTestModule,
+    author: b"Fish in a Barrel Contributors",
+    description: b"A module for testing debugfs",
+    license: b"GPL"
+);
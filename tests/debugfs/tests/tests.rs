@@ -0,0 +1,59 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use kernel_module_testlib::{assert_dmesg_contains, with_kernel_module};
+
+const DIR: &str = "/sys/kernel/debug/rust-debugfs-tests";
+
+fn read(name: &str) -> String {
+    fs::read_to_string(Path::new(DIR).join(name)).unwrap()
+}
+
+#[test]
+fn test_typed_files() {
+    with_kernel_module(|| {
+        assert_eq!(read("u32"), "42\n");
+        assert_eq!(read("x32"), "0x0000beef\n");
+        assert_eq!(read("u64"), "1099511627776\n");
+        assert_eq!(read("bool"), "Y\n");
+        assert_eq!(read("blob"), "blob contents\n");
+        assert_eq!(read("sub/u32"), "42\n");
+    });
+}
+
+#[test]
+fn test_write_typed_files() {
+    with_kernel_module(|| {
+        fs::write(Path::new(DIR).join("u32"), "7").unwrap();
+        fs::write(Path::new(DIR).join("bool"), "N").unwrap();
+        assert_eq!(read("u32"), "7\n");
+        assert_eq!(read("sub/u32"), "7\n");
+        assert_eq!(read("bool"), "N\n");
+    });
+    assert_dmesg_contains(&[b"debugfs values: 7 0xbeef 1099511627776 false"]);
+}
+
+#[test]
+fn test_file_operations() {
+    with_kernel_module(|| {
+        assert_eq!(read("greeting"), "hello, world\n");
+    });
+}
+
+#[test]
+fn test_blob_is_read_only() {
+    with_kernel_module(|| {
+        let metadata = fs::metadata(Path::new(DIR).join("writable-blob")).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o444);
+        assert_eq!(read("writable-blob"), "blob contents\n");
+    });
+}
+
+#[test]
+fn test_removed_on_unload() {
+    with_kernel_module(|| {
+        assert!(Path::new(DIR).exists());
+    });
+    assert!(!Path::new(DIR).exists());
+}
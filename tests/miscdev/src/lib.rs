@@ -0,0 +1,64 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+
+use linux_kernel_module::file_operations::{File, FileOperations, Inode};
+use linux_kernel_module::user_ptr::UserSlicePtrWriter;
+use linux_kernel_module::{self, cstr, miscdev, Mode};
+
+struct GreetingFile {
+    greeting: &'static str,
+}
+
+impl FileOperations for GreetingFile {
+    type Context = &'static str;
+
+    linux_kernel_module::declare_file_operations!(read);
+
+    fn open(
+        context: &Arc<&'static str>,
+        _inode: &Inode,
+        _file: &File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        Ok(GreetingFile {
+            greeting: **context,
+        })
+    }
+
+    fn read(
+        &self,
+        _file: &File,
+        buf: &mut UserSlicePtrWriter,
+        offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        if offset != 0 {
+            return Ok(());
+        }
+        buf.write(self.greeting.as_bytes())
+    }
+}
+
+struct MiscdevTestModule {
+    _registration: miscdev::Registration<GreetingFile>,
+}
+
+impl linux_kernel_module::KernelModule for MiscdevTestModule {
+    fn init() -> linux_kernel_module::KernelResult<Self> {
+        Ok(MiscdevTestModule {
+            _registration: miscdev::Registration::register(
+                cstr!("miscdev-tests"),
+                Mode::from_int(0o444),
+                Arc::new("hello from a misc device"),
+            )?,
+        })
+    }
+}
+
+linux_kernel_module::kernel_module!(
+    MiscdevTestModule,
+    author: b"Fish in a Barrel Contributors",
+    description: b"A module for testing misc devices",
+    license: b"GPL"
+);
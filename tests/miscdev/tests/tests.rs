@@ -0,0 +1,30 @@
+use std::fs;
+use std::path::Path;
+
+use kernel_module_testlib::with_kernel_module;
+
+#[test]
+fn test_registered() {
+    with_kernel_module(|| {
+        let misc = fs::read_to_string("/proc/misc").unwrap();
+        assert!(misc.lines().any(|l| l.ends_with(" miscdev-tests")));
+    });
+}
+
+#[test]
+fn test_read() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/dev/miscdev-tests").unwrap(),
+            "hello from a misc device"
+        );
+    });
+}
+
+#[test]
+fn test_node_removed_after_module_unloaded() {
+    with_kernel_module(|| {
+        assert!(Path::new("/dev/miscdev-tests").exists());
+    });
+    assert!(!Path::new("/dev/miscdev-tests").exists());
+}
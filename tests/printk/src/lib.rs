@@ -1,7 +1,15 @@
 #![no_std]
 #![allow(clippy::print_literal)]
 
-use linux_kernel_module::{self, println};
+use linux_kernel_module::printk::{print_hex_dump, DumpPrefix, Level};
+use linux_kernel_module::{
+    self, pr_debug, pr_err, pr_info, pr_info_once, pr_info_ratelimited, pr_warn, println,
+};
+
+// A single ratelimited callsite, reached both on load and on unload.
+fn ratelimited(i: u32) {
+    pr_info_ratelimited!("pr_info_ratelimited {}", i);
+}
 
 struct PrintkTestModule;
 
@@ -10,11 +18,43 @@ impl linux_kernel_module::KernelModule for PrintkTestModule {
         println!("Single element printk");
         println!();
         println!("printk with {} parameters{}", 2, "!");
+        pr_err!("pr_err message");
+        pr_warn!("pr_warn with {} parameters{}", 2, "!");
+        pr_info!("pr_info message");
+        pr_debug!("pr_debug message");
+        println!("{:>600}", "long line end");
+        pr_info!("first line\nsecond line");
+        for i in 0..3 {
+            pr_info_once!("pr_info_once {}", i);
+        }
+        // Only the first DEFAULT_RATELIMIT_BURST (10) of these are printed.
+        for i in 0..20 {
+            ratelimited(i);
+        }
+        print_hex_dump(
+            Level::Info,
+            "dump: ",
+            DumpPrefix::Offset,
+            16,
+            1,
+            b"Hello, world!\x00\x01abcdef",
+            true,
+        );
 
         Ok(PrintkTestModule)
     }
 }
 
+impl Drop for PrintkTestModule {
+    fn drop(&mut self) {
+        // Printed after the tests had a chance to enable dynamic debug.
+        pr_debug!("pr_debug on exit");
+        // Reports the messages suppressed on load if the ratelimit interval
+        // has passed since.
+        ratelimited(20);
+    }
+}
+
 linux_kernel_module::kernel_module!(
     PrintkTestModule,
     author: b"Fish in a Barrel Contributors",
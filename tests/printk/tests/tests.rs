@@ -1,4 +1,11 @@
-use kernel_module_testlib::{assert_dmesg_contains, with_kernel_module};
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use kernel_module_testlib::{assert_dmesg_contains, assert_dmesg_not_contains, with_kernel_module};
+
+const DYNAMIC_DEBUG_CONTROL: &str = "/sys/kernel/debug/dynamic_debug/control";
 
 #[test]
 fn test_printk() {
@@ -6,3 +13,71 @@ fn test_printk() {
         assert_dmesg_contains(&[b"Single element printk", b"", b"printk with 2 parameters!"]);
     });
 }
+
+#[test]
+fn test_pr_macros() {
+    with_kernel_module(|| {
+        assert_dmesg_contains(&[
+            b"testmodule: pr_err message",
+            b"testmodule: pr_warn with 2 parameters!",
+            b"testmodule: pr_info message",
+        ]);
+    });
+}
+
+#[test]
+fn test_pr_once_and_hex_dump() {
+    with_kernel_module(|| {
+        assert_dmesg_contains(&[
+            b"testmodule: pr_info_once 0",
+            b"dump: 00000000: 48 65 6c 6c 6f 2c 20 77 6f 72 6c 64 21 00 01 61  Hello, world!..a",
+        ]);
+        assert_dmesg_not_contains(b"testmodule: pr_info_once 1");
+        assert_dmesg_not_contains(b"testmodule: pr_info_once 2");
+    });
+}
+
+#[test]
+fn test_pr_ratelimited() {
+    with_kernel_module(|| {
+        assert_dmesg_contains(&[
+            b"testmodule: pr_info_ratelimited 0",
+            b"testmodule: pr_info_ratelimited 9",
+        ]);
+        assert_dmesg_not_contains(b"testmodule: pr_info_ratelimited 10");
+        assert_dmesg_not_contains(b"testmodule: pr_info_ratelimited 19");
+        // Let DEFAULT_RATELIMIT_INTERVAL (5 seconds) pass, so that the
+        // message printed on unload starts a new interval.
+        thread::sleep(Duration::from_secs(6));
+    });
+    assert_dmesg_contains(&[
+        b"testmodule: pr_info_ratelimited 9",
+        b": 10 callbacks suppressed",
+        b"testmodule: pr_info_ratelimited 20",
+    ]);
+}
+
+#[test]
+fn test_long_and_multiline() {
+    with_kernel_module(|| {
+        let long_line = format!("{:>600}", "long line end");
+        assert_dmesg_contains(&[
+            long_line.as_bytes(),
+            b"testmodule: first line",
+            b"testmodule: second line",
+        ]);
+    });
+}
+
+#[test]
+fn test_pr_debug_dynamic_debug() {
+    if !Path::new(DYNAMIC_DEBUG_CONTROL).exists() {
+        // Without CONFIG_DYNAMIC_DEBUG, pr_debug is compiled out.
+        return;
+    }
+    with_kernel_module(|| {
+        assert_dmesg_not_contains(b"testmodule: pr_debug message");
+        fs::write(DYNAMIC_DEBUG_CONTROL, "module testmodule +p").unwrap();
+    });
+    assert_dmesg_contains(&[b"testmodule: pr_debug on exit"]);
+}
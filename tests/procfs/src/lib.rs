@@ -0,0 +1,124 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::format;
+use alloc::sync::Arc;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use linux_kernel_module::file_operations::{File, FileOperations, Inode};
+use linux_kernel_module::procfs::ProcDirEntry;
+use linux_kernel_module::seq_file::{SeqFile, SeqOperations, SeqShow};
+use linux_kernel_module::user_ptr::UserSlicePtrWriter;
+use linux_kernel_module::{self, cstr, Mode};
+
+const LINES: u64 = 1000;
+
+struct Lines {
+    count: u64,
+}
+
+impl SeqOperations for Lines {
+    type Context = u64;
+    type Item = u64;
+
+    fn open(context: &Arc<u64>) -> linux_kernel_module::KernelResult<Self> {
+        Ok(Lines { count: **context })
+    }
+
+    fn start(&self, pos: u64) -> Option<u64> {
+        if pos < self.count {
+            Some(pos)
+        } else {
+            None
+        }
+    }
+
+    fn next(&self, _item: u64, pos: u64) -> Option<u64> {
+        self.start(pos)
+    }
+
+    fn show(&self, m: &mut SeqFile, item: &u64) -> linux_kernel_module::KernelResult<()> {
+        let _ = writeln!(m, "line {}", item);
+        Ok(())
+    }
+}
+
+struct Summary {
+    lines: u64,
+}
+
+impl SeqShow for Summary {
+    type Context = u64;
+
+    fn open(context: &Arc<u64>) -> linux_kernel_module::KernelResult<Self> {
+        Ok(Summary { lines: **context })
+    }
+
+    fn show(&self, m: &mut SeqFile) -> linux_kernel_module::KernelResult<()> {
+        let _ = writeln!(m, "lines: {}", self.lines);
+        Ok(())
+    }
+}
+
+struct OpenCounter {
+    opens: u64,
+}
+
+impl FileOperations for OpenCounter {
+    type Context = AtomicU64;
+
+    linux_kernel_module::declare_file_operations!(read);
+
+    fn open(
+        context: &Arc<AtomicU64>,
+        _inode: &Inode,
+        _file: &File,
+    ) -> linux_kernel_module::KernelResult<Self> {
+        Ok(OpenCounter {
+            opens: context.fetch_add(1, Ordering::Relaxed) + 1,
+        })
+    }
+
+    fn read(
+        &self,
+        _file: &File,
+        buf: &mut UserSlicePtrWriter,
+        offset: u64,
+    ) -> linux_kernel_module::KernelResult<()> {
+        let data = format!("opens: {}\n", self.opens);
+        if offset == 0 && buf.len() >= data.len() {
+            buf.write(data.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+struct ProcfsTestModule {
+    _dir: ProcDirEntry,
+}
+
+impl linux_kernel_module::KernelModule for ProcfsTestModule {
+    fn init() -> linux_kernel_module::KernelResult<Self> {
+        let mut dir = ProcDirEntry::mkdir(cstr!("procfs-tests"), Mode::S_IRUGO | Mode::S_IXUGO)?;
+        dir.add_seq::<Lines>(cstr!("lines"), Mode::S_IRUGO, Arc::new(LINES))?;
+        dir.add_single::<Summary>(cstr!("summary"), Mode::S_IRUGO, Arc::new(LINES))?;
+        let counter = dir.add_file_data::<OpenCounter>(
+            cstr!("counter"),
+            Mode::S_IRUGO,
+            Arc::new(AtomicU64::new(0)),
+        )?;
+        counter.set_size(4096);
+        counter.set_user(1000, 1000);
+        dir.add_symlink(cstr!("link"), cstr!("summary"))?;
+        Ok(ProcfsTestModule { _dir: dir })
+    }
+}
+
+linux_kernel_module::kernel_module!(
+    ProcfsTestModule,
+    author: b"Fish in a Barrel Contributors",
+    description: b"A module for testing procfs",
+    license: b"GPL"
+);
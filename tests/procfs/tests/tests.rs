@@ -0,0 +1,93 @@
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use kernel_module_testlib::with_kernel_module;
+
+fn expected_lines() -> String {
+    (0..1000).map(|i| format!("line {}\n", i)).collect()
+}
+
+#[test]
+fn test_seq_file() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/proc/procfs-tests/lines").unwrap(),
+            expected_lines()
+        );
+    });
+}
+
+#[test]
+fn test_seq_file_short_reads() {
+    with_kernel_module(|| {
+        let mut f = fs::File::open("/proc/procfs-tests/lines").unwrap();
+        let mut data = vec![];
+        let mut buf = [0; 7];
+        loop {
+            let n = f.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(String::from_utf8(data).unwrap(), expected_lines());
+    });
+}
+
+#[test]
+fn test_single_open() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/proc/procfs-tests/summary").unwrap(),
+            "lines: 1000\n"
+        );
+    });
+}
+
+#[test]
+fn test_create_data() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/proc/procfs-tests/counter").unwrap(),
+            "opens: 1\n"
+        );
+        assert_eq!(
+            fs::read_to_string("/proc/procfs-tests/counter").unwrap(),
+            "opens: 2\n"
+        );
+    });
+}
+
+#[test]
+fn test_set_size_and_user() {
+    with_kernel_module(|| {
+        let metadata = fs::metadata("/proc/procfs-tests/counter").unwrap();
+        assert_eq!(metadata.len(), 4096);
+        assert_eq!(metadata.uid(), 1000);
+        assert_eq!(metadata.gid(), 1000);
+    });
+}
+
+#[test]
+fn test_symlink() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_link("/proc/procfs-tests/link").unwrap(),
+            Path::new("summary")
+        );
+        assert_eq!(
+            fs::read_to_string("/proc/procfs-tests/link").unwrap(),
+            "lines: 1000\n"
+        );
+    });
+}
+
+#[test]
+fn test_removed_on_unload() {
+    with_kernel_module(|| {
+        assert!(Path::new("/proc/procfs-tests/lines").exists());
+    });
+    assert!(!Path::new("/proc/procfs-tests").exists());
+}
@@ -2,7 +2,7 @@
 
 extern crate alloc;
 
-use linux_kernel_module::sysctl::{Sysctl, SysctlStorage};
+use linux_kernel_module::sysctl::{Sysctl, SysctlBuffer, SysctlStorage};
 use linux_kernel_module::{self, cstr, random, Mode};
 
 struct EntropySource;
@@ -13,15 +13,10 @@ impl SysctlStorage for EntropySource {
         (data.len(), Ok(()))
     }
 
-    fn read_value(
-        &self,
-        data: &mut linux_kernel_module::user_ptr::UserSlicePtrWriter,
-    ) -> (usize, linux_kernel_module::KernelResult<()>) {
-        let mut storage = alloc::vec![0; data.len()];
-        if let Err(e) = random::getrandom(&mut storage) {
-            return (0, Err(e));
-        }
-        (storage.len(), data.write(&storage))
+    fn read_value(&self, buf: &mut SysctlBuffer) -> linux_kernel_module::KernelResult<()> {
+        let mut storage = alloc::vec![0; buf.requested()];
+        random::getrandom(&mut storage)?;
+        buf.write(&storage)
     }
 }
 
@@ -1,19 +1,38 @@
 #![no_std]
 
-use core::sync::atomic::AtomicBool;
+extern crate alloc;
 
-use linux_kernel_module::{self, cstr};
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64};
 
-use linux_kernel_module::sysctl::Sysctl;
-use linux_kernel_module::Mode;
+use linux_kernel_module::{self, cstr, kformat};
+
+use linux_kernel_module::sysctl::{
+    Bounded, Directory, OnChange, Registration, Sysctl, SysctlChoice, SysctlString,
+};
+use linux_kernel_module::{Error, KernelResult, Mode};
+
+fn check_even(value: &u32) -> KernelResult<()> {
+    if value % 2 != 0 {
+        return Err(Error::EINVAL);
+    }
+    Ok(())
+}
 
 struct SysctlTestModule {
     _sysctl_a: Sysctl<AtomicBool>,
     _sysctl_b: Sysctl<AtomicBool>,
+    _sysctl_int: Sysctl<AtomicI32>,
+    _sysctl_bounded: Sysctl<Bounded<AtomicU64>>,
+    _tree: Registration,
+    _sysctl_string: Sysctl<SysctlString>,
+    _sysctl_choice: Sysctl<SysctlChoice>,
+    _sysctl_even: Sysctl<OnChange<AtomicU32, fn(&u32) -> KernelResult<()>>>,
 }
 
 impl linux_kernel_module::KernelModule for SysctlTestModule {
     fn init() -> linux_kernel_module::KernelResult<Self> {
+        let name_b = kformat!("{}", 'b')?;
         Ok(SysctlTestModule {
             _sysctl_a: Sysctl::register(
                 cstr!("rust/sysctl-tests"),
@@ -23,10 +42,58 @@ impl linux_kernel_module::KernelModule for SysctlTestModule {
             )?,
             _sysctl_b: Sysctl::register(
                 cstr!("rust/sysctl-tests"),
-                cstr!("b"),
+                name_b.as_cstr(),
                 AtomicBool::new(false),
                 Mode::from_int(0o666),
             )?,
+            _sysctl_int: Sysctl::register(
+                cstr!("rust/sysctl-tests"),
+                cstr!("int"),
+                AtomicI32::new(-1),
+                Mode::from_int(0o666),
+            )?,
+            _sysctl_bounded: Sysctl::register(
+                cstr!("rust/sysctl-tests"),
+                cstr!("bounded"),
+                Bounded::new(AtomicU64::new(10), 1, 100),
+                Mode::from_int(0o666),
+            )?,
+            _tree: Directory::new()
+                .entry(
+                    cstr!("x"),
+                    Arc::new(AtomicBool::new(true)),
+                    Mode::from_int(0o666),
+                )
+                .subdirectory(
+                    cstr!("sub"),
+                    Directory::new().entry(
+                        cstr!("y"),
+                        Arc::new(AtomicU32::new(7)),
+                        Mode::from_int(0o644),
+                    ),
+                )
+                .register(cstr!("rust/sysctl-tests/tree"))?,
+            _sysctl_string: Sysctl::register(
+                cstr!("rust/sysctl-tests"),
+                cstr!("string"),
+                SysctlString::new(b"hello", 8)?,
+                Mode::from_int(0o666),
+            )?,
+            _sysctl_choice: Sysctl::register(
+                cstr!("rust/sysctl-tests"),
+                cstr!("choice"),
+                SysctlChoice::new(&["off", "on", "auto"], 2),
+                Mode::from_int(0o666),
+            )?,
+            _sysctl_even: Sysctl::register(
+                cstr!("rust/sysctl-tests"),
+                cstr!("even"),
+                OnChange::new(
+                    AtomicU32::new(0),
+                    check_even as fn(&u32) -> KernelResult<()>,
+                ),
+                Mode::from_int(0o666),
+            )?,
         })
     }
 }
@@ -1,4 +1,6 @@
 use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::fs::FileExt;
 use std::path::Path;
 
 use kernel_module_testlib::with_kernel_module;
@@ -35,6 +37,210 @@ fn test_write_bool_whitespace() {
     });
 }
 
+#[test]
+fn test_read_int_default() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/int").unwrap(),
+            "-1\n"
+        );
+    });
+}
+
+#[test]
+fn test_write_int() {
+    with_kernel_module(|| {
+        fs::write("/proc/sys/rust/sysctl-tests/int", "-42\n").unwrap();
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/int").unwrap(),
+            "-42\n"
+        );
+        fs::write("/proc/sys/rust/sysctl-tests/int", "0x1f").unwrap();
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/int").unwrap(),
+            "31\n"
+        );
+    });
+}
+
+#[test]
+fn test_write_int_invalid() {
+    with_kernel_module(|| {
+        for value in &["abc", "0x", "2147483648", "1 2"] {
+            let err = fs::write("/proc/sys/rust/sysctl-tests/int", value).unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        }
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/int").unwrap(),
+            "-1\n"
+        );
+    });
+}
+
+#[test]
+fn test_write_bounded() {
+    with_kernel_module(|| {
+        fs::write("/proc/sys/rust/sysctl-tests/bounded", "100").unwrap();
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/bounded").unwrap(),
+            "100\n"
+        );
+        for value in &["0", "101"] {
+            let err = fs::write("/proc/sys/rust/sysctl-tests/bounded", value).unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        }
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/bounded").unwrap(),
+            "100\n"
+        );
+    });
+}
+
+#[test]
+fn test_tree() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/tree/x").unwrap(),
+            "1\n"
+        );
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/tree/sub/y").unwrap(),
+            "7\n"
+        );
+        fs::write("/proc/sys/rust/sysctl-tests/tree/sub/y", "8").unwrap();
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/tree/sub/y").unwrap(),
+            "8\n"
+        );
+    });
+    assert!(!Path::new("/proc/sys/rust/sysctl-tests/tree").exists());
+}
+
+#[test]
+fn test_string() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/string").unwrap(),
+            "hello\n"
+        );
+        fs::write("/proc/sys/rust/sysctl-tests/string", "hi there\nignored").unwrap();
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/string").unwrap(),
+            "hi there\n"
+        );
+        fs::write("/proc/sys/rust/sysctl-tests/string", "much too long").unwrap();
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/string").unwrap(),
+            "much too\n"
+        );
+    });
+}
+
+#[test]
+fn test_choice() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/choice").unwrap(),
+            "auto\n"
+        );
+        fs::write("/proc/sys/rust/sysctl-tests/choice", "on\n").unwrap();
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/choice").unwrap(),
+            "on\n"
+        );
+        let err = fs::write("/proc/sys/rust/sysctl-tests/choice", "maybe").unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/choice").unwrap(),
+            "on\n"
+        );
+    });
+}
+
+#[test]
+fn test_on_change() {
+    with_kernel_module(|| {
+        fs::write("/proc/sys/rust/sysctl-tests/even", "4").unwrap();
+        let err = fs::write("/proc/sys/rust/sysctl-tests/even", "5").unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/even").unwrap(),
+            "4\n"
+        );
+    });
+}
+
+#[test]
+fn test_read_byte_by_byte() {
+    with_kernel_module(|| {
+        let mut f = fs::File::open("/proc/sys/rust/sysctl-tests/int").unwrap();
+        let mut value = vec![];
+        let mut byte = [0];
+        while f.read(&mut byte).unwrap() == 1 {
+            value.push(byte[0]);
+        }
+        assert_eq!(value, b"-1\n");
+    });
+}
+
+#[test]
+fn test_write_int_at_offset_is_ignored() {
+    with_kernel_module(|| {
+        let mut f = fs::OpenOptions::new()
+            .write(true)
+            .open("/proc/sys/rust/sysctl-tests/int")
+            .unwrap();
+        assert_eq!(f.write(b"1").unwrap(), 1);
+        assert_eq!(f.write(b"2").unwrap(), 0);
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/int").unwrap(),
+            "1\n"
+        );
+    });
+}
+
+#[test]
+fn test_write_string_appends() {
+    with_kernel_module(|| {
+        let mut f = fs::OpenOptions::new()
+            .write(true)
+            .open("/proc/sys/rust/sysctl-tests/string")
+            .unwrap();
+        f.write_all(b"ab").unwrap();
+        f.write_all(b"cd").unwrap();
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/string").unwrap(),
+            "abcd\n"
+        );
+    });
+}
+
+#[test]
+fn test_write_string_past_end_is_ignored() {
+    with_kernel_module(|| {
+        let f = fs::OpenOptions::new()
+            .write(true)
+            .open("/proc/sys/rust/sysctl-tests/string")
+            .unwrap();
+        assert_eq!(f.write_at(b"ab", 0).unwrap(), 2);
+        assert_eq!(f.write_at(b"cd", 10).unwrap(), 0);
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/string").unwrap(),
+            "ab\n"
+        );
+    });
+}
+
+#[test]
+fn test_formatted_name() {
+    with_kernel_module(|| {
+        assert_eq!(
+            fs::read_to_string("/proc/sys/rust/sysctl-tests/b").unwrap(),
+            "0\n"
+        );
+    });
+}
+
 #[test]
 fn test_file_doesnt_exit_after_module_unloaded() {
     with_kernel_module(|| {
@@ -1,12 +1,24 @@
 #![no_std]
 
+use linux_kernel_module::{println, CStr};
+
 struct UtilsTestModule;
 
 #[allow(dead_code)]
 const TEST_CSTR: linux_kernel_module::CStr<'static> = linux_kernel_module::cstr!("abc");
 
+fn check_cstr(name: &str, bytes: &[u8]) {
+    match CStr::from_bytes_with_nul(bytes) {
+        Ok(s) => println!("{}: ok {}", name, &s[..s.len() - 1]),
+        Err(e) => println!("{}: error {}", name, e.to_kernel_errno()),
+    }
+}
+
 impl linux_kernel_module::KernelModule for UtilsTestModule {
     fn init() -> linux_kernel_module::KernelResult<Self> {
+        check_cstr("cstr valid", b"abc\0");
+        check_cstr("cstr missing nul", b"abc");
+        check_cstr("cstr interior nul", b"a\0bc\0");
         Ok(UtilsTestModule)
     }
 }
@@ -1,6 +1,17 @@
-use kernel_module_testlib::with_kernel_module;
+use kernel_module_testlib::{assert_dmesg_contains, with_kernel_module};
 
 #[test]
 fn test_module_loads() {
     with_kernel_module(|| {});
 }
+
+#[test]
+fn test_cstr_from_bytes_with_nul() {
+    with_kernel_module(|| {
+        assert_dmesg_contains(&[
+            b"cstr valid: ok abc",
+            b"cstr missing nul: error -22",
+            b"cstr interior nul: error -22",
+        ]);
+    });
+}